@@ -0,0 +1,245 @@
+use crate::card::Card;
+use crate::deal::Deal;
+use crate::variant::Rules;
+
+/// A place cards can be moved from or to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pile {
+    Cascade(usize),
+    Cell(usize),
+    Foundation(usize),
+}
+
+/// Moving the last `count` cards of a pile onto another.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Move {
+    pub from: Pile,
+    pub to: Pile,
+    pub count: usize,
+}
+
+/// The state of a game as plain data, independent of how it is displayed.
+#[derive(Clone, Debug)]
+pub struct Board {
+    pub rules: Rules,
+    pub cascades: Vec<Vec<Card>>,
+    pub cells: Vec<Option<Card>>,
+    pub foundations: Vec<Vec<Card>>,
+}
+
+impl From<&Deal> for Board {
+    fn from(deal: &Deal) -> Self {
+        Self {
+            rules: deal.rules(),
+            cascades: deal.cascades.clone(),
+            cells: deal.cells.clone(),
+            foundations: deal.foundations.clone(),
+        }
+    }
+}
+
+impl Board {
+    pub fn is_won(&self) -> bool {
+        self.foundations.iter().all(|f| f.len() >= 13)
+    }
+
+    pub fn empty_cell_count(&self) -> usize {
+        self.cells.iter().filter(|c| c.is_none()).count()
+    }
+
+    pub fn empty_cascade_count(&self) -> usize {
+        self.cascades.iter().filter(|c| c.is_empty()).count()
+    }
+
+    /// How many cards can be moved at once onto `to`.
+    pub fn movable_cards(&self, to: Pile) -> usize {
+        let mut empty_cascades = self.empty_cascade_count();
+        if let Pile::Cascade(i) = to {
+            if self.cascades[i].is_empty() {
+                empty_cascades -= 1;
+            }
+        }
+        self.rules
+            .movable_cards(self.empty_cell_count(), empty_cascades)
+    }
+
+    /// Length of the run of cards built down at the bottom of a cascade.
+    pub fn sequence_length(&self, cascade: usize) -> usize {
+        let cards = &self.cascades[cascade];
+        if cards.is_empty() {
+            return 0;
+        }
+        let mut n = 1;
+        while n < cards.len()
+            && self
+                .rules
+                .builds_down(&cards[cards.len() - n], &cards[cards.len() - n - 1])
+        {
+            n += 1;
+        }
+        n
+    }
+
+    /// The last `n` cards of a pile, if it holds that many.
+    fn top_cards(&self, pile: Pile, n: usize) -> Option<&[Card]> {
+        match pile {
+            Pile::Cascade(i) => {
+                let cascade = self.cascades.get(i)?;
+                cascade.get(cascade.len().checked_sub(n)?..)
+            }
+            Pile::Cell(i) if n == 1 => self.cells.get(i)?.as_ref().map(std::slice::from_ref),
+            Pile::Foundation(i) if n == 1 => {
+                self.foundations.get(i)?.last().map(std::slice::from_ref)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `m` can be played, taking back cards from the foundations
+    /// included.
+    pub fn is_legal(&self, m: &Move) -> bool {
+        if m.count == 0 || m.from == m.to {
+            return false;
+        }
+        let cards = match self.top_cards(m.from, m.count) {
+            Some(cards) => cards,
+            None => return false,
+        };
+        match m.to {
+            Pile::Cascade(i) => {
+                let target = match self.cascades.get(i) {
+                    Some(target) => target,
+                    None => return false,
+                };
+                let fits = match target.last() {
+                    Some(bottom) => self.rules.builds_down(&cards[0], bottom),
+                    None => self.rules.starts_cascade(&cards[0]),
+                };
+                let is_sequence = match m.from {
+                    Pile::Cascade(j) => m.count <= self.sequence_length(j),
+                    _ => true,
+                };
+                fits && is_sequence && m.count <= self.movable_cards(m.to)
+            }
+            Pile::Cell(i) => m.count == 1 && self.cells.get(i) == Some(&None),
+            Pile::Foundation(i) => {
+                m.count == 1
+                    && match self.foundations.get(i) {
+                        Some(foundation) => self.rules.builds_up(&cards[0], foundation.last()),
+                        None => false,
+                    }
+            }
+        }
+    }
+
+    /// Play `m`, which must be legal.
+    pub fn apply(&mut self, m: &Move) {
+        let cards = match m.from {
+            Pile::Cascade(i) => {
+                let cascade = &mut self.cascades[i];
+                cascade.split_off(cascade.len() - m.count)
+            }
+            Pile::Cell(i) => self.cells[i].take().into_iter().collect(),
+            Pile::Foundation(i) => self.foundations[i].pop().into_iter().collect(),
+        };
+        match m.to {
+            Pile::Cascade(i) => self.cascades[i].extend(cards),
+            Pile::Cell(i) => self.cells[i] = cards.into_iter().next(),
+            Pile::Foundation(i) => self.foundations[i].extend(cards),
+        }
+    }
+
+    /// The first foundation `card` can be put on.
+    pub fn foundation_for(&self, card: &Card) -> Option<usize> {
+        self.foundations
+            .iter()
+            .position(|f| self.rules.builds_up(card, f.last()))
+    }
+
+    /// Every legal move, except taking cards back from the foundations.
+    /// Moves which only differ by which empty cell or cascade they go to are
+    /// listed once.
+    pub fn moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let first_empty_cell = self.cells.iter().position(|c| c.is_none());
+        let first_empty_cascade = self.cascades.iter().position(|c| c.is_empty());
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            if let Some(card) = cell {
+                let from = Pile::Cell(i);
+                if let Some(f) = self.foundation_for(card) {
+                    moves.push(Move {
+                        from,
+                        to: Pile::Foundation(f),
+                        count: 1,
+                    });
+                }
+                for (j, cascade) in self.cascades.iter().enumerate() {
+                    let fits = match cascade.last() {
+                        Some(bottom) => self.rules.builds_down(card, bottom),
+                        None => Some(j) == first_empty_cascade && self.rules.starts_cascade(card),
+                    };
+                    if fits {
+                        moves.push(Move {
+                            from,
+                            to: Pile::Cascade(j),
+                            count: 1,
+                        });
+                    }
+                }
+            }
+        }
+
+        for (i, cascade) in self.cascades.iter().enumerate() {
+            let card = match cascade.last() {
+                Some(card) => card,
+                None => continue,
+            };
+            let from = Pile::Cascade(i);
+            if let Some(f) = self.foundation_for(card) {
+                moves.push(Move {
+                    from,
+                    to: Pile::Foundation(f),
+                    count: 1,
+                });
+            }
+            let sequence = self.sequence_length(i);
+            for (j, target) in self.cascades.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let to = Pile::Cascade(j);
+                match target.last() {
+                    Some(bottom) => {
+                        let limit = sequence.min(self.movable_cards(to));
+                        let fitting = (1..=limit).find(|&count| {
+                            self.rules
+                                .builds_down(&cascade[cascade.len() - count], bottom)
+                        });
+                        if let Some(count) = fitting {
+                            moves.push(Move { from, to, count });
+                        }
+                    }
+                    None if Some(j) == first_empty_cascade => {
+                        // Moving a whole cascade to an empty one changes nothing.
+                        let limit = sequence.min(self.movable_cards(to)).min(cascade.len() - 1);
+                        for count in 1..=limit {
+                            if self.rules.starts_cascade(&cascade[cascade.len() - count]) {
+                                moves.push(Move { from, to, count });
+                            }
+                        }
+                    }
+                    None => {}
+                }
+            }
+            if let Some(c) = first_empty_cell {
+                moves.push(Move {
+                    from,
+                    to: Pile::Cell(c),
+                    count: 1,
+                });
+            }
+        }
+        moves
+    }
+}
@@ -1,33 +1,79 @@
+use serde::{Deserialize, Serialize};
+
 pub const CARD_HEIGHT: i32 = 96;
 pub const CARD_WIDTH: i32 = 71;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub struct Card {
     pub suit: u8,
     pub value: u8,
+    /// Which deck the card comes from, so that identical cards of a
+    /// multi-deck game stay distinct.
+    pub deck: u8,
+}
+
+/// Whether `suit` is hearts or diamonds, suits being numbered after the
+/// rows of the tilesheet: spades, hearts, clubs then diamonds.
+pub fn is_red_suit(suit: u8) -> bool {
+    suit % 2 == 1
 }
 
 impl Card {
-    pub fn is_ace(&self) -> bool {
-        self.value == 0
-    }
     pub fn is_red(&self) -> bool {
-        self.suit % 2 == 0
+        is_red_suit(self.suit)
+    }
+    /// The position of the card in a sequence starting from `base_rank`,
+    /// wrapping around from king to ace.
+    pub fn rank(&self, base_rank: u8) -> u8 {
+        (self.value + 13 - base_rank) % 13
     }
-    pub fn follows(&self, previous: &Card) -> bool {
-        previous.suit == self.suit && previous.value + 1 == self.value
+    pub fn follows(&self, previous: &Card, base_rank: u8) -> bool {
+        previous.suit == self.suit && previous.rank(base_rank) + 1 == self.rank(base_rank)
     }
-    pub fn follows_alternating(&self, previous: &Card) -> bool {
-        previous.is_red() != self.is_red() && previous.value + 1 == self.value
+    pub fn follows_alternating(&self, previous: &Card, base_rank: u8) -> bool {
+        previous.is_red() != self.is_red() && previous.rank(base_rank) + 1 == self.rank(base_rank)
     }
 
-    pub fn deck() -> Vec<Card> {
+    pub fn deck(decks: u8) -> Vec<Card> {
         let mut d = Vec::new();
-        for suit in 0..4 {
-            for value in 0..13 {
-                d.push(Card { suit, value })
+        for deck in 0..decks {
+            for suit in 0..4 {
+                for value in 0..13 {
+                    d.push(Card { suit, value, deck })
+                }
             }
         }
         d
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn hearts_and_diamonds_are_red() {
+        // Suits are numbered after the rows of the tilesheet.
+        let red: Vec<bool> = (0..4)
+            .map(|suit| {
+                Card {
+                    suit,
+                    value: 0,
+                    deck: 0,
+                }
+                .is_red()
+            })
+            .collect();
+        assert_eq!(red, [false, true, false, true]);
+    }
+
+    #[test]
+    fn decks_are_told_apart() {
+        let cards = Card::deck(2);
+        assert_eq!(cards.len(), 104);
+        let distinct: HashSet<&Card> = cards.iter().collect();
+        assert_eq!(distinct.len(), 104);
+        assert!(cards.iter().all(|card| card.deck < 2));
+    }
+}
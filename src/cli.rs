@@ -0,0 +1,416 @@
+use crate::deal::{Deal, DealId, NUMBERED_DEALS};
+use crate::game::{Progress, Start};
+use crate::notation;
+use crate::save::SavedGame;
+use crate::variant::Variant;
+use rand::prelude::*;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: freecell [OPTIONS]
+       freecell solve [DEAL] [--nodes N]
+       freecell validate [--variant NAME] FILE
+       freecell replay [DEAL] MOVES
+       freecell tui [DEAL] [--plain]
+       freecell rate [--variant NAME]
+
+Commands:
+  solve      Print a solution of the deal, one move per line
+  validate   Check that a text file describes a complete layout
+  replay     Play the moves of a file, checking that they are legal and win
+  tui        Play in the terminal
+  rate       Rate the difficulty of every numbered deal not rated yet, caching
+             the ratings for deals of a given difficulty
+
+Options:
+      --deal N         Play the numbered deal N, from 1 to 32000
+      --seed N         Play the deal shuffled from the seed N
+      --variant NAME   Play a variant: freecell, double, forecell, penguin or bakers
+      --load FILE      Resume a saved game
+      --board FILE     Play the layout described in a text file
+      --windowed WxH   Open a window of W by H pixels
+      --mute           Play without sound
+      --nodes N        Positions the solver may explore, 200000 by default
+      --plain          Read keys line by line and print the table without colour
+  -h, --help           Print this help
+
+DEAL is one of --deal, --seed, --load or --board, along with --variant.
+MOVES is a file of moves in standard notation, or - to read them from the
+standard input.
+
+Exit status: 0 on success, 1 when a deal has no solution, a layout is invalid
+or moves do not win, 2 on usage errors, and 3 when the solver gives up.
+";
+
+/// Positions the `solve` command may explore unless told otherwise.
+const DEFAULT_NODES: usize = 200_000;
+
+/// How the first game is set up.
+#[derive(Debug, PartialEq)]
+pub enum Opening {
+    Random,
+    Deal(DealId),
+    Load(PathBuf),
+    Board(PathBuf),
+}
+
+/// Options to launch the game with.
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub variant: Option<Variant>,
+    pub opening: Opening,
+    pub window_size: Option<(f32, f32)>,
+    pub mute: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play(Options),
+    Solve { options: Options, nodes: usize },
+    Validate(Options),
+    Replay { options: Options, moves: PathBuf },
+    Tui { options: Options, plain: bool },
+    Rate(Variant),
+    Help,
+}
+
+/// A command line which could not be understood.
+#[derive(Debug, PartialEq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, CliError> {
+    args.next()
+        .ok_or_else(|| CliError(format!("{} expects a value", option)))
+}
+
+fn number<T: std::str::FromStr>(s: &str, option: &str) -> Result<T, CliError> {
+    s.parse()
+        .map_err(|_| CliError(format!("{} expects a number, got \"{}\"", option, s)))
+}
+
+fn window_size(s: &str) -> Result<(f32, f32), CliError> {
+    let bad = || {
+        CliError(format!(
+            "--windowed expects a size such as 1024x768, got \"{}\"",
+            s
+        ))
+    };
+    let (width, height) = s.split_once('x').ok_or_else(bad)?;
+    let width: u32 = width.parse().map_err(|_| bad())?;
+    let height: u32 = height.parse().map_err(|_| bad())?;
+    if width == 0 || height == 0 {
+        return Err(bad());
+    }
+    Ok((width as f32, height as f32))
+}
+
+/// Read the command line arguments, without the program name.
+pub fn parse(args: impl Iterator<Item = String>) -> Result<Command, CliError> {
+    let mut args = args.peekable();
+    let subcommand = match args.peek().map(String::as_str) {
+        Some("solve") | Some("validate") | Some("replay") | Some("tui") | Some("rate") => {
+            args.next()
+        }
+        _ => None,
+    };
+
+    let mut variant = None;
+    let mut opening = Opening::Random;
+    let mut window = None;
+    let mut mute = false;
+    let mut nodes = None;
+    let mut plain = false;
+    let mut positional = Vec::new();
+
+    let mut set_opening = |new: Opening, option: &str| {
+        if opening != Opening::Random {
+            return Err(CliError(format!(
+                "{} cannot be used with --deal, --seed, --load or --board",
+                option
+            )));
+        }
+        opening = new;
+        Ok(())
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--deal" => {
+                let n = number(&value(&mut args, &arg)?, &arg)?;
+                if !NUMBERED_DEALS.contains(&n) {
+                    return Err(CliError(format!(
+                        "--deal expects a number from {} to {}, got {}",
+                        NUMBERED_DEALS.start(),
+                        NUMBERED_DEALS.end(),
+                        n
+                    )));
+                }
+                set_opening(Opening::Deal(DealId::Number(n)), &arg)?;
+            }
+            "--seed" => {
+                let seed = number(&value(&mut args, &arg)?, &arg)?;
+                set_opening(Opening::Deal(DealId::Seed(seed)), &arg)?;
+            }
+            "--load" => set_opening(Opening::Load(value(&mut args, &arg)?.into()), &arg)?,
+            "--board" => set_opening(Opening::Board(value(&mut args, &arg)?.into()), &arg)?,
+            "--variant" => {
+                let id = value(&mut args, &arg)?;
+                let ids: Vec<&str> = Variant::ALL.iter().map(|v| v.id).collect();
+                variant = Some(Variant::from_id(&id).ok_or_else(|| {
+                    CliError(format!(
+                        "Unknown variant \"{}\", expected one of: {}",
+                        id,
+                        ids.join(", ")
+                    ))
+                })?);
+            }
+            "--windowed" => window = Some(window_size(&value(&mut args, &arg)?)?),
+            "--mute" => mute = true,
+            "--nodes" => nodes = Some(number(&value(&mut args, &arg)?, &arg)?),
+            "--plain" => plain = true,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(CliError(format!("Unexpected argument \"{}\"", arg)))
+            }
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    if let (Some(_), Opening::Load(_)) = (variant, &opening) {
+        return Err(CliError(
+            "--variant cannot be used with --load, the saved game has its own".to_string(),
+        ));
+    }
+    let subcommand = match subcommand {
+        Some(subcommand) => subcommand,
+        None => {
+            if let Some(path) = positional.first() {
+                return Err(CliError(format!(
+                    "Unexpected argument \"{}\"",
+                    path.display()
+                )));
+            }
+            if nodes.is_some() {
+                return Err(CliError("--nodes only applies to solve".to_string()));
+            }
+            if plain {
+                return Err(CliError("--plain only applies to tui".to_string()));
+            }
+            return Ok(Command::Play(Options {
+                variant,
+                opening,
+                window_size: window,
+                mute,
+            }));
+        }
+    };
+
+    if window.is_some() || mute {
+        return Err(CliError(format!(
+            "--windowed and --mute do not apply to {}",
+            subcommand
+        )));
+    }
+    if nodes.is_some() && subcommand != "solve" {
+        return Err(CliError(format!(
+            "--nodes does not apply to {}",
+            subcommand
+        )));
+    }
+    if plain && subcommand != "tui" {
+        return Err(CliError(format!(
+            "--plain does not apply to {}",
+            subcommand
+        )));
+    }
+    let mut positional = positional.into_iter();
+    let mut file = |what: &str| {
+        positional
+            .next()
+            .ok_or_else(|| CliError(format!("{} expects {}", subcommand, what)))
+    };
+    if (subcommand == "solve" || subcommand == "replay") && opening == Opening::Random {
+        return Err(CliError(format!(
+            "{} expects a deal: --deal, --seed, --load or --board",
+            subcommand
+        )));
+    }
+    if subcommand == "rate" && opening != Opening::Random {
+        return Err(CliError(
+            "rate rates every numbered deal rather than a given deal".to_string(),
+        ));
+    }
+    let command = match subcommand.as_str() {
+        "solve" => Command::Solve {
+            options: Options {
+                variant,
+                opening,
+                window_size: None,
+                mute: false,
+            },
+            nodes: nodes.unwrap_or(DEFAULT_NODES),
+        },
+        "validate" => {
+            if opening != Opening::Random {
+                return Err(CliError(
+                    "validate expects a layout file rather than a deal".to_string(),
+                ));
+            }
+            Command::Validate(Options {
+                variant,
+                opening: Opening::Board(file("a layout file")?),
+                window_size: None,
+                mute: false,
+            })
+        }
+        "tui" => Command::Tui {
+            options: Options {
+                variant,
+                opening,
+                window_size: None,
+                mute: false,
+            },
+            plain,
+        },
+        "rate" => Command::Rate(variant.unwrap_or(Variant::FREECELL)),
+        _ => Command::Replay {
+            moves: file("a file of moves")?,
+            options: Options {
+                variant,
+                opening,
+                window_size: None,
+                mute: false,
+            },
+        },
+    };
+    if let Some(path) = positional.next() {
+        return Err(CliError(format!(
+            "Unexpected argument \"{}\"",
+            path.display()
+        )));
+    }
+    Ok(command)
+}
+
+/// Deal or read the first game described by `options`, of `variant` unless
+/// the options name another.
+pub fn start(options: Options, variant: Variant) -> Result<Start, CliError> {
+    let variant = options.variant.unwrap_or(variant);
+    let (deal, deal_id, progress) = match options.opening {
+        Opening::Random => {
+            let id = DealId::Seed(rand::thread_rng().gen());
+            (Deal::from_id(variant, id), Some(id), None)
+        }
+        Opening::Deal(id) => (Deal::from_id(variant, id), Some(id), None),
+        Opening::Load(path) => {
+            let error = |e| CliError(format!("Could not load {}: {}", path.display(), e));
+            let saved = SavedGame::load(&path).map_err(error)?;
+            let progress = Progress {
+                moves: saved.moves,
+                penalty: saved.penalty,
+                elapsed: Duration::from_secs(saved.seconds),
+            };
+            (saved.position().map_err(error)?, saved.deal, Some(progress))
+        }
+        Opening::Board(path) => {
+            let error = |e: &dyn fmt::Display| {
+                CliError(format!("Could not read {}: {}", path.display(), e))
+            };
+            let text = fs::read_to_string(&path).map_err(|e| error(&e))?;
+            let deal = notation::parse_layout(variant, &text).map_err(|e| error(&e))?;
+            (deal, None, None)
+        }
+    };
+    Ok(Start {
+        deal,
+        deal_id,
+        progress,
+        window_size: options.window_size,
+        muted: options.mute,
+        audio: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Command, CliError> {
+        parse(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn options_configure_the_game() {
+        assert_eq!(
+            parse_line("--deal 617 --variant bakers --windowed 800x600 --mute"),
+            Ok(Command::Play(Options {
+                variant: Some(Variant::BAKERS_GAME),
+                opening: Opening::Deal(DealId::Number(617)),
+                window_size: Some((800., 600.)),
+                mute: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn windowed_expects_a_size() {
+        for size in ["800", "800x", "x600", "0x600", "800x600x2", "axb"] {
+            assert!(
+                parse_line(&format!("--windowed {}", size)).is_err(),
+                "{}",
+                size
+            );
+        }
+        assert!(parse_line("--windowed").is_err());
+    }
+
+    #[test]
+    fn one_opening_at_a_time() {
+        for line in [
+            "--seed 1 --deal 2",
+            "--deal 2 --load save.json",
+            "--load save.json --board layout.txt",
+        ] {
+            assert!(parse_line(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn deals_are_numbered() {
+        assert!(parse_line("--deal 0").is_err());
+        assert!(parse_line("--deal 32001").is_err());
+        assert!(parse_line("--deal one").is_err());
+    }
+
+    #[test]
+    fn variants_are_known() {
+        assert_eq!(
+            parse_line("--variant spider"),
+            Err(CliError(
+                "Unknown variant \"spider\", expected one of: \
+                 freecell, double, forecell, penguin, bakers"
+                    .to_string()
+            ))
+        );
+        assert!(parse_line("--load save.json --variant penguin").is_err());
+    }
+
+    #[test]
+    fn unexpected_arguments_are_refused() {
+        assert!(parse_line("--fullscreen").is_err());
+        assert!(parse_line("layout.txt").is_err());
+        assert!(parse_line("--plain").is_err());
+        assert!(parse_line("solve --deal 1 --mute").is_err());
+    }
+}
@@ -1,18 +1,59 @@
 use crate::game::Collision;
 use ggez::event::EventHandler;
-use ggez::graphics::{DrawParam, Drawable, Image};
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Drawable, Image, Mesh, Rect, Text};
 use ggez::{Context, GameResult};
-use nalgebra::{point, Vector2};
+use nalgebra::{point, vector, Vector2};
+
+/// What a button shows.
+enum Face {
+    Image(Image),
+    Label(Text),
+}
 
 pub struct Button {
     pos: Vector2<i32>,
-    image: Image,
+    size: Vector2<i32>,
+    face: Face,
+    highlighted: bool,
 }
 
 impl Button {
     pub fn new(ctx: &mut Context, pos: Vector2<i32>) -> Self {
         let image = Image::new(ctx, "/icon.png").unwrap();
-        Self { image, pos }
+        let size = vector![image.width() as i32, image.height() as i32];
+        Self {
+            pos,
+            size,
+            face: Face::Image(image),
+            highlighted: false,
+        }
+    }
+
+    /// A button showing `label`, to be placed with `set_pos`.
+    pub fn label(label: &str, size: Vector2<i32>) -> Self {
+        Self {
+            pos: vector![0, 0],
+            size,
+            face: Face::Label(Text::new(label)),
+            highlighted: false,
+        }
+    }
+
+    pub fn set_pos(&mut self, pos: Vector2<i32>) {
+        self.pos = pos;
+    }
+
+    pub fn set_size(&mut self, size: Vector2<i32>) {
+        self.size = size;
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.face = Face::Label(Text::new(label));
+    }
+
+    /// Show the button as the one selected, such as with the keyboard.
+    pub fn set_highlighted(&mut self, highlighted: bool) {
+        self.highlighted = highlighted;
     }
 }
 
@@ -22,17 +63,41 @@ impl EventHandler<ggez::GameError> for Button {
     }
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
         let pos = point![self.pos[0] as f32, self.pos[1] as f32];
-        self.image.draw(ctx, DrawParam::default().dest(pos))
+        match &self.face {
+            Face::Image(image) => image.draw(ctx, DrawParam::default().dest(pos)),
+            Face::Label(text) => {
+                let color = if self.highlighted {
+                    let bounds = Rect::new(pos.x, pos.y, self.size[0] as f32, self.size[1] as f32);
+                    let background = Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        bounds,
+                        Color::new(1., 1., 1., 0.15),
+                    )?;
+                    graphics::draw(ctx, &background, DrawParam::default())?;
+                    Color::YELLOW
+                } else {
+                    Color::WHITE
+                };
+                // Centre the label vertically.
+                let y = pos.y + (self.size[1] as f32 - text.height(ctx)) / 2.;
+                graphics::draw(
+                    ctx,
+                    text,
+                    DrawParam::default()
+                        .dest(point![pos.x + 8., y])
+                        .color(color),
+                )
+            }
+        }
     }
 }
 
 impl Collision for Button {
     fn inside(&self, pos: Vector2<i32>) -> bool {
-        let dim = self.image.dimensions();
-
         pos[0] >= self.pos[0]
-            && pos[0] <= self.pos[0] + dim.w as i32
+            && pos[0] <= self.pos[0] + self.size[0]
             && pos[1] >= self.pos[1]
-            && pos[1] <= self.pos[1] + dim.h as i32
+            && pos[1] <= self.pos[1] + self.size[1]
     }
 }
@@ -1,28 +1,39 @@
-use super::CARD_STACK_INCREMENT;
+use super::{Highlight, CARD_STACK_INCREMENT};
 use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
 use crate::game::Collision;
-use crate::tileset::{TileParams, TileSet};
+use crate::tileset::TileSet;
+use crate::variant::Rules;
 use ggez::event::EventHandler;
+use ggez::graphics::Rect;
 use ggez::{Context, GameResult};
-use nalgebra::{point, Vector2};
+use nalgebra::{point, vector, Vector2};
 use std::sync::{Arc, Mutex};
 
 pub struct Cascade {
     pos: Vector2<i32>,
     cards: Vec<Card>,
+    rules: Rules,
     tileset: Arc<Mutex<TileSet<Option<Card>>>>,
+    highlight: Highlight,
+    /// The number of cards at the bottom under the cursor, and how they are
+    /// tinted.
+    hover: (usize, Highlight),
 }
 
 impl Cascade {
     pub fn new(
         pos: Vector2<i32>,
         cards: Vec<Card>,
+        rules: Rules,
         tileset: Arc<Mutex<TileSet<Option<Card>>>>,
     ) -> Self {
         Self {
             pos,
             cards,
+            rules,
             tileset,
+            highlight: Highlight::None,
+            hover: (0, Highlight::None),
         }
     }
 
@@ -37,23 +48,28 @@ impl Cascade {
         self.cards.append(&mut cards);
     }
 
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
     pub fn is_empty(&self) -> bool {
         self.cards.is_empty()
     }
 
     pub fn cards_to_take(&self, pos: Vector2<i32>) -> usize {
-        if !self.inside(pos) {
+        if !self.inside(pos) || self.is_empty() {
             0
         } else {
             for i in 0..(self.cards.len()) {
                 if pos[1] <= (self.pos[1] + ((1 + i as i32) * CARD_STACK_INCREMENT)) {
-                    return self.cards.len() - i as usize;
+                    return self.cards.len() - i;
                 }
             }
             1
         }
     }
-    pub fn has_alternating_color_cards(&self, n: usize) -> bool {
+    /// Whether the last `n` cards are built down according to the rules.
+    pub fn has_sequence(&self, n: usize) -> bool {
         let mut it = (self.cards[(self.cards.len() - n)..(self.cards.len())]).iter();
         let mut prev_card = if let Some(first_card) = it.next() {
             first_card
@@ -61,7 +77,7 @@ impl Cascade {
             return true;
         };
         for current_card in it {
-            if !(prev_card.follows_alternating(current_card)) {
+            if !self.rules.builds_down(current_card, prev_card) {
                 return false;
             }
             prev_card = current_card;
@@ -74,10 +90,36 @@ impl Cascade {
 
     pub fn can_stack(&self, card: &Card) -> bool {
         match self.bottom_card() {
-            Some(column_bottom_card) => column_bottom_card.follows_alternating(card),
-            None => true,
+            Some(column_bottom_card) => self.rules.builds_down(card, column_bottom_card),
+            None => self.rules.starts_cascade(card),
+        }
+    }
+    /// Where the next card put on the cascade is drawn.
+    pub fn next_pos(&self) -> Vector2<i32> {
+        self.pos + vector![0, self.cards.len() as i32 * CARD_STACK_INCREMENT]
+    }
+    /// The area covered by the cascade's cards.
+    pub fn bounds(&self) -> Rect {
+        let (x, y) = (self.pos[0] as f32, self.pos[1] as f32);
+        Rect::new(x, y, CARD_WIDTH as f32, self.height() as f32)
+    }
+
+    fn height(&self) -> i32 {
+        if self.cards.is_empty() {
+            CARD_HEIGHT
+        } else {
+            (self.cards.len() as i32 - 1) * CARD_STACK_INCREMENT + CARD_HEIGHT
         }
     }
+
+    pub fn set_highlight(&mut self, highlight: Highlight) {
+        self.highlight = highlight;
+    }
+    /// Tint the last `cards` cards with `highlight`, over the tint of the
+    /// cascade.
+    pub fn set_hover(&mut self, cards: usize, highlight: Highlight) {
+        self.hover = (cards, highlight);
+    }
 }
 
 impl EventHandler<ggez::GameError> for Cascade {
@@ -89,17 +131,24 @@ impl EventHandler<ggez::GameError> for Cascade {
             self.tileset
                 .lock()
                 .unwrap()
-                .queue_tile(None, self.pos, None::<TileParams>)
+                .queue_tile(None, self.pos, self.highlight.params())
                 .unwrap();
         } else {
+            let (hovered, hover) = self.hover;
+            let first_hovered = self.cards.len().saturating_sub(hovered);
             for (y, card) in self.cards.iter().cloned().enumerate() {
+                let highlight = if y >= first_hovered {
+                    hover
+                } else {
+                    self.highlight
+                };
                 self.tileset
                     .lock()
                     .unwrap()
                     .queue_tile(
                         Some(card),
                         point![0, (y as i32 * CARD_STACK_INCREMENT)] + self.pos,
-                        None::<TileParams>,
+                        highlight.params(),
                     )
                     .unwrap();
             }
@@ -110,12 +159,7 @@ impl EventHandler<ggez::GameError> for Cascade {
 
 impl Collision for Cascade {
     fn inside(&self, pos: Vector2<i32>) -> bool {
-        let height = if self.cards.is_empty() {
-            CARD_HEIGHT
-        } else {
-            (self.cards.len() as i32 - 1) * CARD_STACK_INCREMENT + CARD_HEIGHT
-        };
-
+        let height = self.height();
         pos[0] >= self.pos[0]
             && pos[0] <= self.pos[0] + CARD_WIDTH
             && pos[1] >= self.pos[1]
@@ -1,7 +1,9 @@
+use super::Highlight;
 use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
 use crate::game::Collision;
-use crate::tileset::{TileParams, TileSet};
+use crate::tileset::TileSet;
 use ggez::event::EventHandler;
+use ggez::graphics::Rect;
 use ggez::{Context, GameResult};
 use nalgebra::Vector2;
 use std::sync::{Arc, Mutex};
@@ -10,6 +12,7 @@ pub struct Cell {
     pos: Vector2<i32>,
     card: Option<Card>,
     tileset: Arc<Mutex<TileSet<Option<Card>>>>,
+    highlight: Highlight,
 }
 
 impl Cell {
@@ -18,7 +21,12 @@ impl Cell {
         card: Option<Card>,
         tileset: Arc<Mutex<TileSet<Option<Card>>>>,
     ) -> Self {
-        Self { pos, card, tileset }
+        Self {
+            pos,
+            card,
+            tileset,
+            highlight: Highlight::None,
+        }
     }
 
     pub fn take(&mut self) -> Option<Card> {
@@ -33,6 +41,16 @@ impl Cell {
     pub fn is_empty(&self) -> bool {
         self.card.is_none()
     }
+    pub fn pos(&self) -> Vector2<i32> {
+        self.pos
+    }
+    pub fn bounds(&self) -> Rect {
+        let (x, y) = (self.pos[0] as f32, self.pos[1] as f32);
+        Rect::new(x, y, CARD_WIDTH as f32, CARD_HEIGHT as f32)
+    }
+    pub fn set_highlight(&mut self, highlight: Highlight) {
+        self.highlight = highlight;
+    }
 }
 
 impl EventHandler<ggez::GameError> for Cell {
@@ -44,7 +62,7 @@ impl EventHandler<ggez::GameError> for Cell {
         self.tileset
             .lock()
             .unwrap()
-            .queue_tile(self.card.clone(), self.pos, None::<TileParams>)
+            .queue_tile(self.card.clone(), self.pos, self.highlight.params())
             .unwrap();
         Ok(())
     }
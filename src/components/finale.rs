@@ -1,56 +1,268 @@
-use crate::card::Card;
+use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
 use crate::tileset::{TileParams, TileSet};
 use ggez::audio::{SoundSource, Source};
 use ggez::event::EventHandler;
-use ggez::timer::check_update_time;
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Text};
 use ggez::{Context, GameResult};
-use nalgebra::vector;
-use nalgebra::Vector2;
+use nalgebra::{point, vector, Vector2};
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Cards scattered over the table at most, the oldest being removed first.
+const SCATTERED_CARDS: usize = 300;
+/// Positions of bouncing cards left on the table at most.
+const TRAIL_LENGTH: usize = 1500;
+/// Pulling bouncing cards down, in pixels per tick per tick.
+const GRAVITY: f32 = 0.5;
+/// Share of their speed bouncing cards keep when hitting the bottom.
+const BOUNCE: f32 = 0.75;
+const PANEL_SIZE: [f32; 2] = [360., 110.];
+
+/// How winning a game is celebrated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Celebration {
+    /// Cards thrown off the foundations one by one, bouncing along the
+    /// bottom of the table and leaving a trail.
+    Bounce,
+    /// Cards scattered all over the table.
+    #[default]
+    Scatter,
+    /// A panel telling how many moves the game took.
+    Panel,
+    None,
+}
+
+impl Celebration {
+    pub const ALL: [Celebration; 4] = [
+        Celebration::Bounce,
+        Celebration::Scatter,
+        Celebration::Panel,
+        Celebration::None,
+    ];
+}
+
+impl std::fmt::Display for Celebration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Celebration::Bounce => "bouncing cards",
+            Celebration::Scatter => "scattered cards",
+            Celebration::Panel => "panel",
+            Celebration::None => "none",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A card bouncing off the table.
+struct Flight {
+    card: Card,
+    pos: Vector2<f32>,
+    velocity: Vector2<f32>,
+}
 
 pub struct Finale {
     tileset: Arc<Mutex<TileSet<Option<Card>>>>,
+    celebration: Celebration,
     finale_card_positions: VecDeque<(Card, Vector2<i32>)>,
-    audio: Source,
+    /// Cards still to throw off the foundations, the next one last, with
+    /// where they are thrown from.
+    launches: Vec<(Card, Vector2<i32>)>,
+    flight: Option<Flight>,
+    panel: Option<Text>,
+    /// Absent without an audio device.
+    audio: Option<Source>,
+    /// When the celebration started, its ticks being counted from then
+    /// rather than from the start of the game.
+    started: Option<Instant>,
+    /// Ticks played so far.
+    ticks: u32,
 }
 
 impl Finale {
-    pub fn new(ctx: &mut Context, tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> Self {
-        let mut audio = Source::new(ctx, "/take.wav").unwrap();
-        audio.set_repeat(true);
-        audio.set_pitch(4.);
-        audio.set_volume(0.6);
-
+    pub fn new(tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> Self {
         Self {
             finale_card_positions: VecDeque::new(),
             tileset,
-            audio,
+            celebration: Celebration::None,
+            launches: Vec::new(),
+            flight: None,
+            panel: None,
+            audio: None,
+            started: None,
+            ticks: 0,
+        }
+    }
+
+    /// Start celebrating a game won in `moves` moves, with the cards of the
+    /// `foundations` at their positions, to the sound of `audio`.
+    pub fn start(
+        &mut self,
+        celebration: Celebration,
+        foundations: &[(Vector2<i32>, Vec<Card>)],
+        moves: u32,
+        audio: Option<Source>,
+    ) {
+        self.celebration = celebration;
+        self.audio = audio;
+        self.started = Some(Instant::now());
+        match celebration {
+            Celebration::Bounce => {
+                // Kings first, going along the foundations, then queens...
+                let height = foundations.iter().map(|(_, cards)| cards.len()).max();
+                for i in 0..height.unwrap_or(0) {
+                    for (pos, cards) in foundations.iter() {
+                        if let Some(card) = cards.iter().rev().nth(i) {
+                            self.launches.push((card.clone(), *pos));
+                        }
+                    }
+                }
+                self.launches.reverse();
+            }
+            Celebration::Panel => {
+                self.panel = Some(Text::new(format!(
+                    "You won in {} moves!\n\nClick or press Space for a new deal",
+                    moves
+                )));
+            }
+            Celebration::Scatter => {}
+            Celebration::None => {}
+        }
+        if let Some(audio) = &mut self.audio {
+            audio.set_repeat(matches!(
+                celebration,
+                Celebration::Bounce | Celebration::Scatter
+            ));
         }
     }
+
+    /// Play the sound from now on at `volume`, such as after muting.
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some(audio) = &mut self.audio {
+            audio.set_volume(volume);
+        }
+    }
+
     pub fn is_playing(&self) -> bool {
-        self.audio.playing()
+        self.ticks > 0
+    }
+
+    /// Throw the next card off the foundations once the last one has left
+    /// the table, and move it.
+    fn bounce(&mut self, screen: Rect) {
+        let mut rng = rand::thread_rng();
+        if self.flight.is_none() {
+            self.flight = self.launches.pop().map(|(card, pos)| {
+                let speed = rng.gen_range(2.0..7.0);
+                Flight {
+                    card,
+                    pos: pos.cast(),
+                    velocity: vector![
+                        if rng.gen() { speed } else { -speed },
+                        -rng.gen_range(0.0..8.0)
+                    ],
+                }
+            });
+        }
+        let flight = match &mut self.flight {
+            Some(flight) => flight,
+            None => return,
+        };
+        flight.velocity.y += GRAVITY;
+        flight.pos += flight.velocity;
+        let floor = screen.bottom() - CARD_HEIGHT as f32;
+        if flight.pos.y > floor {
+            flight.pos.y = floor;
+            flight.velocity.y *= -BOUNCE;
+        }
+        if self.finale_card_positions.len() >= TRAIL_LENGTH {
+            self.finale_card_positions.pop_front();
+        }
+        let pos = vector![flight.pos.x.round() as i32, flight.pos.y.round() as i32];
+        self.finale_card_positions
+            .push_back((flight.card.clone(), pos));
+        if flight.pos.x + (CARD_WIDTH as f32) < screen.left() || flight.pos.x > screen.right() {
+            self.flight = None;
+        }
+    }
+
+    fn scatter(&mut self, screen: Rect) {
+        let mut rng = rand::thread_rng();
+        if self.finale_card_positions.len() >= SCATTERED_CARDS {
+            self.finale_card_positions.pop_front();
+        }
+        let (left, top) = (screen.left() as i32, screen.top() as i32);
+        let (right, bottom) = (screen.right() as i32, screen.bottom() as i32);
+        self.finale_card_positions.push_back((
+            Card::deck(1).into_iter().choose(&mut rng).unwrap(),
+            vector![
+                rng.gen_range(left - 10..right + 10),
+                rng.gen_range(top - 10..bottom + 10)
+            ],
+        ));
+    }
+
+    /// Draw the panel of the celebration, if any, over the cards.
+    pub fn draw_panel(&self, ctx: &mut Context) -> GameResult<()> {
+        let text = match &self.panel {
+            Some(text) => text,
+            None => return Ok(()),
+        };
+        let screen = graphics::screen_coordinates(ctx);
+        let [width, height] = PANEL_SIZE;
+        let bounds = Rect::new(
+            screen.x + (screen.w - width) / 2.,
+            screen.y + (screen.h - height) / 2.,
+            width,
+            height,
+        );
+        let background =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), bounds, Color::new(0., 0., 0., 0.85))?;
+        graphics::draw(ctx, &background, DrawParam::default())?;
+        let size = text.dimensions(ctx);
+        let pos = point![
+            bounds.x + (width - size.w) / 2.,
+            bounds.y + (height - size.h) / 2.
+        ];
+        graphics::draw(
+            ctx,
+            text,
+            DrawParam::default().dest(pos).color(Color::WHITE),
+        )
     }
 }
 
 impl EventHandler<ggez::GameError> for Finale {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        if check_update_time(ctx, 35) {
-            let mut rng = rand::thread_rng();
-            if self.finale_card_positions.len() >= 300 {
-                self.finale_card_positions.pop_front();
+        let started = match self.started {
+            Some(started) => started,
+            None => return Ok(()),
+        };
+        let screen = graphics::screen_coordinates(ctx);
+        let rate = match self.celebration {
+            Celebration::Bounce => 60.,
+            _ => 35.,
+        };
+        // The first tick comes right away.
+        let due = (started.elapsed().as_secs_f32() * rate) as u32 + 1;
+        while self.ticks < due {
+            match self.celebration {
+                Celebration::Bounce => self.bounce(screen),
+                Celebration::Scatter => self.scatter(screen),
+                Celebration::Panel | Celebration::None => {}
             }
-            self.finale_card_positions.push_back((
-                Card::deck().into_iter().choose(&mut rng).unwrap(),
-                vector![rng.gen_range(-10..810), rng.gen_range(-10..610)],
-            ));
 
-            if !self.is_playing() {
-                self.audio.play(ctx)?;
-            } else {
-                self.audio.set_volume(self.audio.volume() * 0.99);
+            if let Some(audio) = &mut self.audio {
+                if self.ticks == 0 {
+                    audio.play(ctx)?;
+                } else if audio.repeat() {
+                    audio.set_volume(audio.volume() * 0.99);
+                }
             }
+            self.ticks += 1;
         }
         Ok(())
     }
@@ -1,7 +1,10 @@
+use super::Highlight;
 use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
 use crate::game::Collision;
-use crate::tileset::{TileParams, TileSet};
+use crate::tileset::TileSet;
+use crate::variant::Rules;
 use ggez::event::EventHandler;
+use ggez::graphics::Rect;
 use ggez::{Context, GameResult};
 use nalgebra::Vector2;
 use std::sync::{Arc, Mutex};
@@ -9,19 +12,24 @@ use std::sync::{Arc, Mutex};
 pub struct Foundation {
     pos: Vector2<i32>,
     cards: Vec<Card>,
+    rules: Rules,
     tileset: Arc<Mutex<TileSet<Option<Card>>>>,
+    highlight: Highlight,
 }
 
 impl Foundation {
     pub fn new(
         pos: Vector2<i32>,
         cards: Vec<Card>,
+        rules: Rules,
         tileset: Arc<Mutex<TileSet<Option<Card>>>>,
     ) -> Self {
         Self {
             pos,
             cards,
+            rules,
             tileset,
+            highlight: Highlight::None,
         }
     }
 
@@ -31,9 +39,15 @@ impl Foundation {
     pub fn put(&mut self, card: Card) {
         self.cards.push(card);
     }
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
     pub fn is_empty(&self) -> bool {
         self.cards.is_empty()
     }
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
 
     pub fn is_full(&self) -> bool {
         self.cards.len() >= 13
@@ -43,10 +57,17 @@ impl Foundation {
         self.cards.last()
     }
     pub fn can_stack(&self, card: &Card) -> bool {
-        match self.top_card() {
-            Some(stack_top_card) => card.follows(stack_top_card),
-            None => card.is_ace(),
-        }
+        self.rules.builds_up(card, self.top_card())
+    }
+    pub fn pos(&self) -> Vector2<i32> {
+        self.pos
+    }
+    pub fn bounds(&self) -> Rect {
+        let (x, y) = (self.pos[0] as f32, self.pos[1] as f32);
+        Rect::new(x, y, CARD_WIDTH as f32, CARD_HEIGHT as f32)
+    }
+    pub fn set_highlight(&mut self, highlight: Highlight) {
+        self.highlight = highlight;
     }
 }
 
@@ -60,7 +81,7 @@ impl EventHandler<ggez::GameError> for Foundation {
             self.tileset
                 .lock()
                 .unwrap()
-                .queue_tile(None, self.pos, None::<TileParams>)
+                .queue_tile(None, self.pos, self.highlight.params())
                 .unwrap();
         } else {
             self.tileset
@@ -69,7 +90,7 @@ impl EventHandler<ggez::GameError> for Foundation {
                 .queue_tile(
                     Some(self.cards.last().unwrap().clone()),
                     self.pos,
-                    None::<TileParams>,
+                    self.highlight.params(),
                 )
                 .unwrap();
         }
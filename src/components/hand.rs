@@ -1,54 +1,60 @@
-use super::CARD_STACK_INCREMENT;
+use super::{table_position, CARD_STACK_INCREMENT};
 use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
+use crate::sound::{Effect, Sounds};
 use crate::tileset::{TileParams, TileSet};
-use ggez::audio::{SoundData, SoundSource, Source};
 use ggez::event::EventHandler;
+use ggez::graphics::Rect;
 use ggez::input;
 use ggez::{Context, GameResult};
 use nalgebra::{point, vector, Vector2};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long cards put down where they cannot go take to fly back.
+const RETURN_DURATION: Duration = Duration::from_millis(200);
+/// How much bigger held cards are drawn, as if lifted from the table.
+const LIFT_SCALE: f32 = 0.06;
+/// Offset of the shadow of held cards.
+const SHADOW_OFFSET: [f32; 2] = [5., 8.];
+
+/// Cards flying back to where they were picked up from.
+struct Return {
+    from: Vector2<i32>,
+    to: Vector2<i32>,
+    started: Instant,
+}
 
 pub struct Hand {
     pos: Vector2<i32>,
     cards: Vec<Card>,
+    returning: Option<Return>,
+    /// How high the cards are lifted, from 0 on the table to 1 in hand.
+    lift: f32,
     tileset: Arc<Mutex<TileSet<Option<Card>>>>,
-    drop_audio: SoundData,
-    take_audio: SoundData,
+    sounds: Arc<Mutex<Sounds>>,
 }
 
 impl Hand {
-    pub fn new(ctx: &mut Context, tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> Self {
-        let drop_audio = SoundData::new(ctx, "/drop.wav").unwrap();
-        let take_audio = SoundData::new(ctx, "/take.wav").unwrap();
+    pub fn new(tileset: Arc<Mutex<TileSet<Option<Card>>>>, sounds: Arc<Mutex<Sounds>>) -> Self {
         Self {
             pos: vector![0, 0],
             cards: Vec::new(),
+            returning: None,
+            lift: 1.,
             tileset,
-            drop_audio,
-            take_audio,
+            sounds,
         }
     }
-    fn play_drop(&self, ctx: &mut Context) {
-        let mut source = Source::from_data(ctx, self.drop_audio.clone()).unwrap();
-        source.set_volume(0.15);
-        source.set_pitch(1.7);
-        source.play_detached(ctx).unwrap();
-    }
-    fn play_take(&self, ctx: &mut Context) {
-        let mut source = Source::from_data(ctx, self.take_audio.clone()).unwrap();
-        source.set_volume(0.15);
-        source.set_pitch(1.7);
-        source.play_detached(ctx).unwrap();
-    }
 
     pub fn take(&mut self, ctx: &mut Context) -> Vec<Card> {
         let mut v = Vec::new();
         std::mem::swap(&mut v, &mut self.cards);
-        self.play_drop(ctx);
+        self.returning = None;
+        self.sounds.lock().unwrap().play(ctx, Effect::Drop);
         v
     }
     pub fn put(&mut self, ctx: &mut Context, mut cards: Vec<Card>) {
-        self.play_take(ctx);
+        self.sounds.lock().unwrap().play(ctx, Effect::Pick);
         self.cards.append(&mut cards);
     }
 
@@ -56,8 +62,40 @@ impl Hand {
         self.cards.is_empty()
     }
 
-    pub fn is_single_card(&self) -> bool {
-        self.cards.len() == 1
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Follow the cursor at `pos`, on the table.
+    pub fn hold_at(&mut self, pos: Vector2<i32>) {
+        self.pos = pos - vector![CARD_WIDTH / 2, CARD_HEIGHT / 3];
+    }
+
+    /// The area covered by the cards in hand.
+    pub fn bounds(&self) -> Rect {
+        let height = (self.cards.len() as i32 - 1).max(0) * CARD_STACK_INCREMENT + CARD_HEIGHT;
+        let (x, y) = (self.pos[0] as f32, self.pos[1] as f32);
+        Rect::new(x, y, CARD_WIDTH as f32, height as f32)
+    }
+
+    /// Fly the cards from where they are to `to`, where they are to be taken
+    /// once `has_returned`.
+    pub fn send_back(&mut self, to: Vector2<i32>) {
+        self.returning = Some(Return {
+            from: self.pos,
+            to,
+            started: Instant::now(),
+        });
+    }
+
+    pub fn is_returning(&self) -> bool {
+        self.returning.is_some()
+    }
+
+    pub fn has_returned(&self) -> bool {
+        self.returning
+            .as_ref()
+            .is_some_and(|r| r.started.elapsed() >= RETURN_DURATION)
     }
 
     pub fn top_card(&self) -> Option<&Card> {
@@ -67,22 +105,39 @@ impl Hand {
 
 impl EventHandler<ggez::GameError> for Hand {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if let Some(r) = &self.returning {
+            let t = (r.started.elapsed().as_secs_f32() / RETURN_DURATION.as_secs_f32()).min(1.);
+            // Slowing down on the way.
+            let eased = 1. - (1. - t) * (1. - t);
+            let way = (r.to - r.from).cast::<f32>() * eased;
+            self.pos = r.from + vector![way.x.round() as i32, way.y.round() as i32];
+            self.lift = 1. - eased;
+            return Ok(());
+        }
+        self.lift = 1.;
         let cursor_pos = input::mouse::position(ctx);
-        self.pos = vector![
-            cursor_pos.x as i32 - CARD_WIDTH / 2,
-            cursor_pos.y as i32 - CARD_HEIGHT / 3
-        ];
+        self.hold_at(table_position(ctx, cursor_pos.x, cursor_pos.y));
         Ok(())
     }
     fn draw(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        let scale = 1. + LIFT_SCALE * self.lift;
+        // Grown around the middle of the cards.
+        let origin = self.pos.cast::<f32>()
+            - vector![CARD_WIDTH as f32, CARD_HEIGHT as f32] * (scale - 1.) / 2.;
+        let params = || TileParams {
+            color: None,
+            scale: Some(vector![scale, scale]),
+            shadow: Some(Vector2::from(SHADOW_OFFSET) * self.lift),
+        };
         for (y, card) in self.cards.iter().cloned().enumerate() {
+            let pos = origin + vector![0., y as f32 * CARD_STACK_INCREMENT as f32 * scale];
             self.tileset
                 .lock()
                 .unwrap()
                 .queue_tile(
                     Some(card),
-                    point![0, (y as i32 * CARD_STACK_INCREMENT)] + self.pos,
-                    None::<TileParams>,
+                    point![pos.x.round() as i32, pos.y.round() as i32],
+                    Some(params()),
                 )
                 .unwrap();
         }
@@ -0,0 +1,43 @@
+use crate::tileset::TileParams;
+use ggez::graphics::Color;
+
+/// Tint of the piles that would take the cards in hand.
+const LEGAL_TINT: Color = Color::new(0.75, 1., 0.7, 1.);
+/// Tint of the piles that would not.
+const ILLEGAL_TINT: Color = Color::new(0.55, 0.55, 0.55, 1.);
+/// Tint of the cards under the cursor that can be picked up.
+const MOVABLE_TINT: Color = Color::new(1., 1., 0.7, 1.);
+/// Tint of those that cannot.
+const STUCK_TINT: Color = Color::new(1., 0.7, 0.7, 1.);
+
+/// How cards are tinted to show what can be done with them: while cards are
+/// held, whether they can be put on a pile, and otherwise whether the cards
+/// under the cursor can be picked up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Highlight {
+    /// Drawn as they are.
+    #[default]
+    None,
+    Legal,
+    Illegal,
+    Movable,
+    Stuck,
+}
+
+impl Highlight {
+    /// The parameters to queue the tiles of the pile with.
+    pub fn params(&self) -> Option<TileParams> {
+        let color = match self {
+            Highlight::None => return None,
+            Highlight::Legal => LEGAL_TINT,
+            Highlight::Illegal => ILLEGAL_TINT,
+            Highlight::Movable => MOVABLE_TINT,
+            Highlight::Stuck => STUCK_TINT,
+        };
+        Some(TileParams {
+            color: Some(color),
+            scale: None,
+            shadow: None,
+        })
+    }
+}
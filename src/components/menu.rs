@@ -0,0 +1,187 @@
+use super::Button;
+use crate::game::Collision;
+use ggez::event::{EventHandler, KeyCode};
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Text};
+use ggez::{Context, GameResult};
+use nalgebra::{point, vector, Vector2};
+
+const WIDTH: i32 = 460;
+const LINE_HEIGHT: i32 = 26;
+const PADDING: i32 = 20;
+
+/// What the player did with a menu.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuEvent {
+    /// An entry was clicked, or chosen with Enter or Space.
+    Activate(usize),
+    /// An entry was changed with the left or right arrow, forward for right.
+    Change(usize, bool),
+    Close,
+}
+
+/// A panel drawn over the middle of the table, with a title, some text and
+/// a list of buttons, one of them selected.
+pub struct Menu {
+    title: Text,
+    text: Vec<Text>,
+    buttons: Vec<Button>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: &str, labels: &[String]) -> Self {
+        let mut menu = Self {
+            title: Text::new(title),
+            text: Vec::new(),
+            buttons: Vec::new(),
+            selected: 0,
+        };
+        menu.set_labels(labels);
+        menu
+    }
+
+    /// Lines of text shown above the buttons.
+    pub fn set_text(&mut self, lines: &[String]) {
+        self.text = lines.iter().map(|line| Text::new(line.as_str())).collect();
+    }
+
+    pub fn set_labels(&mut self, labels: &[String]) {
+        if labels.len() == self.buttons.len() {
+            for (button, label) in self.buttons.iter_mut().zip(labels) {
+                button.set_label(label);
+            }
+        } else {
+            let size = vector![WIDTH - PADDING, LINE_HEIGHT];
+            self.buttons = labels.iter().map(|l| Button::label(l, size)).collect();
+            self.selected = self.selected.min(self.buttons.len().saturating_sub(1));
+        }
+        self.highlight();
+    }
+
+    pub fn select(&mut self, i: usize) {
+        self.selected = i;
+        self.highlight();
+    }
+
+    fn highlight(&mut self) {
+        for (i, button) in self.buttons.iter_mut().enumerate() {
+            button.set_highlighted(i == self.selected);
+        }
+    }
+
+    /// The number of lines of the menu, the title taking two.
+    fn lines(&self) -> i32 {
+        (2 + self.text.len() + self.buttons.len() + usize::from(!self.text.is_empty())) as i32
+    }
+
+    /// The height of the lines, smaller than usual if needed for the menu to
+    /// fit on the screen.
+    fn line_height(&self, ctx: &Context) -> i32 {
+        let screen = graphics::screen_coordinates(ctx);
+        LINE_HEIGHT.min((screen.h as i32 - 2 * PADDING) / self.lines())
+    }
+
+    fn bounds(&self, ctx: &Context) -> Rect {
+        let screen = graphics::screen_coordinates(ctx);
+        let height = (2 * PADDING + self.lines() * self.line_height(ctx)) as f32;
+        Rect::new(
+            screen.x + (screen.w - WIDTH as f32) / 2.,
+            screen.y + (screen.h - height) / 2.,
+            WIDTH as f32,
+            height,
+        )
+    }
+
+    /// Place the buttons in the middle of the screen.
+    fn layout(&mut self, ctx: &Context) -> Rect {
+        let bounds = self.bounds(ctx);
+        let line_height = self.line_height(ctx);
+        let x = bounds.x as i32 + PADDING / 2;
+        let mut y = bounds.y as i32 + PADDING + 2 * line_height;
+        if !self.text.is_empty() {
+            y += (self.text.len() + 1) as i32 * line_height;
+        }
+        for button in self.buttons.iter_mut() {
+            button.set_pos(vector![x, y]);
+            button.set_size(vector![WIDTH - PADDING, line_height]);
+            y += line_height;
+        }
+        bounds
+    }
+
+    pub fn key(&mut self, keycode: KeyCode) -> Option<MenuEvent> {
+        let count = self.buttons.len();
+        match keycode {
+            KeyCode::Escape => return Some(MenuEvent::Close),
+            _ if count == 0 => {}
+            KeyCode::Up => self.select((self.selected + count - 1) % count),
+            KeyCode::Down | KeyCode::Tab => self.select((self.selected + 1) % count),
+            KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space => {
+                return Some(MenuEvent::Activate(self.selected))
+            }
+            KeyCode::Left => return Some(MenuEvent::Change(self.selected, false)),
+            KeyCode::Right => return Some(MenuEvent::Change(self.selected, true)),
+            _ => {}
+        }
+        None
+    }
+
+    /// Select the button under `pos`, in table coordinates.
+    pub fn hover(&mut self, ctx: &Context, pos: Vector2<i32>) {
+        self.layout(ctx);
+        if let Some(i) = self.buttons.iter().position(|b| b.inside(pos)) {
+            self.select(i);
+        }
+    }
+
+    /// Activate the button under `pos`, or close the menu when clicking
+    /// outside of it.
+    pub fn click(&mut self, ctx: &Context, pos: Vector2<i32>) -> Option<MenuEvent> {
+        let bounds = self.layout(ctx);
+        if let Some(i) = self.buttons.iter().position(|b| b.inside(pos)) {
+            self.select(i);
+            return Some(MenuEvent::Activate(i));
+        }
+        if bounds.contains(point![pos[0] as f32, pos[1] as f32]) {
+            None
+        } else {
+            Some(MenuEvent::Close)
+        }
+    }
+}
+
+impl EventHandler<ggez::GameError> for Menu {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let bounds = self.layout(ctx);
+        let line_height = self.line_height(ctx) as f32;
+        let background =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), bounds, Color::new(0., 0., 0., 0.85))?;
+        graphics::draw(ctx, &background, DrawParam::default())?;
+
+        let x = bounds.x + PADDING as f32;
+        let mut y = bounds.y + PADDING as f32;
+        graphics::draw(
+            ctx,
+            &self.title,
+            DrawParam::default().dest(point![x, y]).color(Color::WHITE),
+        )?;
+        y += 2. * line_height;
+        for line in self.text.iter() {
+            graphics::draw(
+                ctx,
+                line,
+                DrawParam::default()
+                    .dest(point![x, y])
+                    .color(Color::from_rgb(200, 200, 200)),
+            )?;
+            y += line_height;
+        }
+        for button in self.buttons.iter_mut() {
+            button.draw(ctx)?;
+        }
+        Ok(())
+    }
+}
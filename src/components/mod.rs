@@ -4,13 +4,35 @@ mod cell;
 mod finale;
 mod foundation;
 mod hand;
+mod highlight;
+mod menu;
+mod status;
+mod table;
 
 use crate::card::CARD_HEIGHT;
+use ggez::graphics;
+use ggez::Context;
+use nalgebra::{vector, Vector2};
 const CARD_STACK_INCREMENT: i32 = CARD_HEIGHT / 4;
 
+/// Convert a position in the window to a position on the table, which may be
+/// scaled to fit the window.
+pub fn table_position(ctx: &Context, x: f32, y: f32) -> Vector2<i32> {
+    let screen = graphics::screen_coordinates(ctx);
+    let (width, height) = graphics::drawable_size(ctx);
+    vector![
+        (screen.x + x * screen.w / width) as i32,
+        (screen.y + y * screen.h / height) as i32
+    ]
+}
+
 pub use button::*;
 pub use cascade::*;
 pub use cell::*;
 pub use finale::*;
 pub use foundation::*;
 pub use hand::*;
+pub use highlight::*;
+pub use menu::*;
+pub use status::*;
+pub use table::*;
@@ -0,0 +1,57 @@
+use ggez::event::EventHandler;
+use ggez::graphics::{self, Color, DrawParam, Text};
+use ggez::{Context, GameResult};
+use nalgebra::{point, Vector2};
+use std::time::{Duration, Instant};
+
+/// How long a notice replaces the status text.
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+/// A line of text at the bottom of the table.
+pub struct StatusBar {
+    pos: Vector2<i32>,
+    text: Text,
+    notice: Option<(Text, Instant)>,
+}
+
+impl StatusBar {
+    pub fn new(pos: Vector2<i32>) -> Self {
+        Self {
+            pos,
+            text: Text::default(),
+            notice: None,
+        }
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = Text::new(text);
+    }
+
+    /// Show `text` in place of the status for a few seconds.
+    pub fn notify(&mut self, text: &str) {
+        self.notice = Some((Text::new(text), Instant::now()));
+    }
+}
+
+impl EventHandler<ggez::GameError> for StatusBar {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        if let Some((_, shown)) = self.notice {
+            if shown.elapsed() >= NOTICE_DURATION {
+                self.notice = None;
+            }
+        }
+        Ok(())
+    }
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let pos = point![self.pos[0] as f32, self.pos[1] as f32];
+        let text = match &self.notice {
+            Some((notice, _)) => notice,
+            None => &self.text,
+        };
+        graphics::draw(
+            ctx,
+            text,
+            DrawParam::default().dest(pos).color(Color::WHITE),
+        )
+    }
+}
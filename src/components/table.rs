@@ -0,0 +1,76 @@
+use ggez::event::EventHandler;
+use ggez::graphics::{self, Color, DrawParam, Image, Rect, WrapMode};
+use ggez::{Context, GameResult};
+use nalgebra::{point, vector};
+use serde::{Deserialize, Serialize};
+
+/// Colour of the table in dark mode, whatever the one set.
+const DARK_COLOUR: Color = Color::new(0.07, 0.08, 0.09, 1.);
+/// Tint dimming the table image in dark mode.
+const DARK_TINT: Color = Color::new(0.35, 0.35, 0.35, 1.);
+
+/// How an image covers the table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fill {
+    /// Repeated from the top left corner.
+    #[default]
+    Tile,
+    /// Stretched to the size of the table.
+    Stretch,
+}
+
+/// The table under the cards: a colour, with an image over it if there is
+/// one.
+pub struct Table {
+    colour: Color,
+    image: Option<(Image, Fill)>,
+    dark: bool,
+}
+
+impl Table {
+    pub fn new(colour: [u8; 3], dark: bool) -> Self {
+        let [r, g, b] = colour;
+        Self {
+            colour: Color::from_rgb(r, g, b),
+            image: None,
+            dark,
+        }
+    }
+
+    pub fn set_colour(&mut self, colour: [u8; 3], dark: bool) {
+        let [r, g, b] = colour;
+        self.colour = Color::from_rgb(r, g, b);
+        self.dark = dark;
+    }
+
+    pub fn set_image(&mut self, image: Option<(Image, Fill)>) {
+        self.image = image.map(|(mut image, fill)| {
+            image.set_wrap(WrapMode::Tile, WrapMode::Tile);
+            (image, fill)
+        });
+    }
+}
+
+impl EventHandler<ggez::GameError> for Table {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, if self.dark { DARK_COLOUR } else { self.colour });
+        let (image, fill) = match &self.image {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+        let screen = graphics::screen_coordinates(ctx);
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let param = DrawParam::default()
+            .dest(point![screen.x, screen.y])
+            .color(if self.dark { DARK_TINT } else { Color::WHITE });
+        let param = match fill {
+            Fill::Tile => param.src(Rect::new(0., 0., screen.w / width, screen.h / height)),
+            Fill::Stretch => param.scale(vector![screen.w / width, screen.h / height]),
+        };
+        graphics::draw(ctx, image, param)
+    }
+}
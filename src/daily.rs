@@ -0,0 +1,75 @@
+use crate::deal::NUMBERED_DEALS;
+use chrono::{Datelike, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A calendar day.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Today in the local time zone.
+    pub fn today() -> Self {
+        let today = Local::today();
+        Date {
+            year: today.year(),
+            month: today.month(),
+            day: today.day(),
+        }
+    }
+
+    /// The number of the deal everyone plays on this day.
+    pub fn deal_number(&self) -> u32 {
+        let mut hash = (self.year as u32)
+            .wrapping_mul(372)
+            .wrapping_add(self.month * 31 + self.day);
+        // Spread consecutive days over the whole range.
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(0x045d_9f3b);
+        hash ^= hash >> 16;
+        NUMBERED_DEALS.start() + hash % (NUMBERED_DEALS.end() - NUMBERED_DEALS.start() + 1)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deal_number_is_fixed_for_a_day_and_numbered() {
+        let date = Date {
+            year: 2021,
+            month: 8,
+            day: 14,
+        };
+        assert_eq!(date.deal_number(), date.deal_number());
+        for year in 2000..2040 {
+            for month in 1..=12 {
+                for day in 1..=31 {
+                    let number = Date { year, month, day }.deal_number();
+                    assert!(NUMBERED_DEALS.contains(&number));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn consecutive_days_get_different_deals() {
+        let day = |day| Date {
+            year: 2021,
+            month: 8,
+            day,
+        };
+        assert_ne!(day(14).deal_number(), day(15).deal_number());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::card::Card;
+use crate::variant::{BaseRank, Rules, Variant};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// The numbered deals, as in the Microsoft game.
+pub const NUMBERED_DEALS: RangeInclusive<u32> = 1..=32000;
+
+/// How a deal was produced, which is enough to deal it again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DealId {
+    /// A shuffle from a random number generator seed.
+    Seed(u64),
+    /// A deal numbered as in the Microsoft game.
+    Number(u32),
+}
+
+/// The layout of a game before any move is made.
+#[derive(Clone, Debug)]
+pub struct Deal {
+    pub variant: Variant,
+    pub cascades: Vec<Vec<Card>>,
+    pub cells: Vec<Option<Card>>,
+    pub foundations: Vec<Vec<Card>>,
+    pub base_rank: u8,
+}
+
+impl Deal {
+    pub fn from_id(variant: Variant, id: DealId) -> Self {
+        match id {
+            DealId::Seed(seed) => Deal::seeded(variant, seed),
+            DealId::Number(number) => Deal::numbered(variant, number),
+        }
+    }
+
+    /// Deal the deck in the order of the Microsoft deal `number`, so that
+    /// classic Freecell deals match their well known layouts.
+    pub fn numbered(variant: Variant, number: u32) -> Self {
+        // Cards are ordered by rank, then clubs, diamonds, hearts and spades.
+        const SUITS: [u8; 4] = [2, 3, 1, 0];
+        let mut cards: Vec<Card> = (0..variant.decks)
+            .flat_map(|deck| {
+                (0..52).map(move |i| Card {
+                    suit: SUITS[i % 4],
+                    value: (i / 4) as u8,
+                    deck,
+                })
+            })
+            .collect();
+
+        let mut seed = number;
+        let mut deck = Vec::with_capacity(cards.len());
+        while !cards.is_empty() {
+            seed = seed.wrapping_mul(214013).wrapping_add(2531011) & 0x7fff_ffff;
+            let j = (seed >> 16) as usize % cards.len();
+            deck.push(cards.swap_remove(j));
+        }
+        deck.reverse();
+        Deal::from_deck(variant, deck)
+    }
+
+    /// Deal the deck shuffled from `seed` for `variant`.
+    pub fn seeded(variant: Variant, seed: u64) -> Self {
+        Deal::shuffled(variant, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Deal a freshly shuffled deck for `variant`.
+    pub fn shuffled(variant: Variant, rng: &mut impl Rng) -> Self {
+        let mut deck = Card::deck(variant.decks);
+        deck.shuffle(rng);
+        Deal::from_deck(variant, deck)
+    }
+
+    /// Deal `deck` for `variant`, cards being dealt from the end of `deck`.
+    ///
+    /// Cards go round the cascades one at a time, except for the last
+    /// `variant.prefilled_cells` which go to the free cells. When the base
+    /// rank is settled by the first card dealt, that card stays on the first
+    /// cascade and the others of its rank start the foundations.
+    pub fn from_deck(variant: Variant, mut deck: Vec<Card>) -> Self {
+        let mut foundations = vec![Vec::new(); variant.foundations()];
+        let base_rank = match variant.base_rank {
+            BaseRank::Ace => 0,
+            BaseRank::FirstDealt => match deck.pop() {
+                Some(first) => {
+                    let base_rank = first.value;
+                    let (bases, mut rest): (Vec<Card>, Vec<Card>) =
+                        deck.into_iter().partition(|c| c.value == base_rank);
+                    for (foundation, card) in foundations.iter_mut().zip(bases) {
+                        foundation.push(card);
+                    }
+                    rest.push(first);
+                    deck = rest;
+                    base_rank
+                }
+                None => 0,
+            },
+        };
+
+        let mut cells = vec![None; variant.cells];
+        let cascade_card_count = deck.len().saturating_sub(variant.prefilled_cells);
+        let mut cascades = vec![Vec::new(); variant.cascades];
+        let mut selected_column = 0;
+        for _ in 0..cascade_card_count {
+            cascades[selected_column].push(deck.pop().unwrap());
+            selected_column = (selected_column + 1) % cascades.len();
+        }
+        for cell in cells.iter_mut() {
+            *cell = deck.pop();
+        }
+
+        Self {
+            variant,
+            cascades,
+            cells,
+            foundations,
+            base_rank,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules::new(self.variant, self.base_rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn cards(deal: &Deal) -> Vec<&Card> {
+        deal.cascades
+            .iter()
+            .flatten()
+            .chain(deal.cells.iter().flatten())
+            .chain(deal.foundations.iter().flatten())
+            .collect()
+    }
+
+    #[test]
+    fn double_deck_deals_every_card_twice() {
+        let deal = Deal::seeded(Variant::DOUBLE_FREECELL, 7);
+        assert_eq!(deal.cascades.len(), 10);
+        assert_eq!(deal.cells.len(), 8);
+        assert_eq!(deal.foundations.len(), 8);
+        let cards = cards(&deal);
+        assert_eq!(cards.len(), 104);
+        let distinct: HashSet<_> = cards.iter().map(|c| (c.suit, c.value, c.deck)).collect();
+        assert_eq!(distinct.len(), 104);
+        let faces: HashSet<_> = cards.iter().map(|c| (c.suit, c.value)).collect();
+        assert_eq!(faces.len(), 52);
+    }
+
+    #[test]
+    fn penguin_starts_the_foundations_from_the_first_card() {
+        // Cards are dealt from the end of the deck: the king of diamonds first.
+        let deal = Deal::from_deck(Variant::PENGUIN, Card::deck(1));
+        let first = Card {
+            suit: 3,
+            value: 12,
+            deck: 0,
+        };
+        assert_eq!(deal.base_rank, 12);
+        assert_eq!(deal.cascades[0][0], first);
+        let bases: Vec<&Card> = deal.foundations.iter().flatten().collect();
+        assert_eq!(bases.len(), 3);
+        assert!(bases.iter().all(|c| c.value == 12 && **c != first));
+        assert_eq!(cards(&deal).len(), 52);
+    }
+
+    #[test]
+    fn forecell_fills_the_cells() {
+        let deal = Deal::from_deck(Variant::FORECELL, Card::deck(1));
+        assert!(deal.cells.iter().all(Option::is_some));
+        assert_eq!(deal.cascades.iter().map(Vec::len).sum::<usize>(), 48);
+        assert!(deal.foundations.iter().all(Vec::is_empty));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::board::Board;
+use crate::solver::{self, Limits, Outcome, Solution};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::time::Instant;
+
+/// Positions the solver may explore when rating a deal.
+const RATING_NODES: usize = 200_000;
+/// Positions the solver may explore for each reduced number of free cells.
+const REDUCED_CELLS_NODES: usize = 20_000;
+const CACHE_FILE: &str = "ratings.json";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    /// The difficulty to request after `requested`, going through every
+    /// difficulty then back to none in particular.
+    pub fn next_request(requested: Option<Difficulty>) -> Option<Difficulty> {
+        match requested {
+            None => Some(Difficulty::Easy),
+            Some(Difficulty::Easy) => Some(Difficulty::Medium),
+            Some(Difficulty::Medium) => Some(Difficulty::Hard),
+            Some(Difficulty::Hard) => Some(Difficulty::Expert),
+            Some(Difficulty::Expert) => None,
+        }
+    }
+
+    /// How many steps apart two difficulties are.
+    pub fn distance(&self, other: &Difficulty) -> usize {
+        let index = |d: &Difficulty| Difficulty::ALL.iter().position(|a| a == d).unwrap();
+        (index(self) as isize - index(other) as isize).unsigned_abs()
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Difficulty::Easy => "easy",
+                Difficulty::Medium => "medium",
+                Difficulty::Hard => "hard",
+                Difficulty::Expert => "expert",
+            }
+        )
+    }
+}
+
+/// The fewest free cells `board` can be won with, trying each count below
+/// what the variant offers, searching until `deadline` at most.
+fn required_cells(board: &Board, deadline: Option<Instant>) -> usize {
+    let cells = board.cells.len();
+    if board.cells.iter().any(|c| c.is_some()) {
+        return cells;
+    }
+    let limits = Limits {
+        nodes: REDUCED_CELLS_NODES,
+        deadline,
+    };
+    (0..cells)
+        .find(|&count| {
+            let mut reduced = board.clone();
+            reduced.cells.truncate(count);
+            matches!(solver::solve(&reduced, &limits), Outcome::Solved(_))
+        })
+        .unwrap_or(cells)
+}
+
+/// Grade a solution from 0 to 9 on search effort, length and the share of
+/// free cells needed.
+fn points(solution: &Solution, cards: usize, required_cells: usize, cells: usize) -> usize {
+    let effort = match solution.nodes {
+        0..=1_999 => 0,
+        2_000..=9_999 => 1,
+        10_000..=49_999 => 2,
+        _ => 3,
+    };
+    let moves_per_card = solution.moves.len() as f32 / cards as f32;
+    let length = if moves_per_card < 1.7 {
+        0
+    } else if moves_per_card < 1.9 {
+        1
+    } else if moves_per_card < 2.1 {
+        2
+    } else {
+        3
+    };
+    let cell_share = required_cells as f32 / cells.max(1) as f32;
+    let cells = if cell_share <= 0.25 {
+        0
+    } else if cell_share <= 0.5 {
+        1
+    } else if cell_share <= 0.75 {
+        2
+    } else {
+        3
+    };
+    effort + length + cells
+}
+
+/// Rate how hard `board` is to win, or `None` if no solution was found.
+///
+/// Past `deadline` the search is cut short, leaving the rating unreliable.
+pub fn rate(board: &Board, deadline: Option<Instant>) -> Option<Difficulty> {
+    let limits = Limits {
+        nodes: RATING_NODES,
+        deadline,
+    };
+    let solution = match solver::solve(board, &limits) {
+        Outcome::Solved(solution) => solution,
+        Outcome::Unsolvable | Outcome::GaveUp => return None,
+    };
+    let cards = 52 * board.rules.variant.decks as usize;
+    let required_cells = required_cells(board, deadline);
+    Some(
+        match points(&solution, cards, required_cells, board.cells.len()) {
+            0..=2 => Difficulty::Easy,
+            3..=4 => Difficulty::Medium,
+            5..=6 => Difficulty::Hard,
+            _ => Difficulty::Expert,
+        },
+    )
+}
+
+/// Ratings of numbered deals kept on disk, by variant id then deal
+/// number. Deals no solution was found for are rated `None`.
+#[derive(Default, Serialize, Deserialize)]
+pub struct RatingCache {
+    ratings: HashMap<String, BTreeMap<u32, Option<Difficulty>>>,
+}
+
+impl RatingCache {
+    pub fn load() -> Self {
+        storage::load(storage::data_file(CACHE_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(storage::data_file(CACHE_FILE), self)
+    }
+
+    pub fn get(&self, variant: &str, number: u32) -> Option<Option<Difficulty>> {
+        self.ratings.get(variant)?.get(&number).copied()
+    }
+
+    pub fn insert(&mut self, variant: &str, number: u32, rating: Option<Difficulty>) {
+        self.ratings
+            .entry(variant.to_string())
+            .or_default()
+            .insert(number, rating);
+    }
+
+    /// Every deal of `variant` rated `difficulty`.
+    pub fn deals(&self, variant: &str, difficulty: Difficulty) -> Vec<u32> {
+        self.ratings
+            .get(variant)
+            .map(|ratings| {
+                ratings
+                    .iter()
+                    .filter(|(_, rating)| **rating == Some(difficulty))
+                    .map(|(number, _)| *number)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The rated deal of `variant` closest in difficulty to `difficulty`.
+    pub fn closest(&self, variant: &str, difficulty: Difficulty) -> Option<u32> {
+        self.ratings
+            .get(variant)?
+            .iter()
+            .filter_map(|(number, rating)| rating.map(|r| (r.distance(&difficulty), *number)))
+            .min()
+            .map(|(_, number)| number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Move, Pile};
+
+    fn solution(moves: usize, nodes: usize) -> Solution {
+        let step = Move {
+            from: Pile::Cascade(0),
+            to: Pile::Cell(0),
+            count: 1,
+        };
+        Solution {
+            moves: vec![step; moves],
+            nodes,
+        }
+    }
+
+    #[test]
+    fn points_grow_with_effort_length_and_cells() {
+        assert_eq!(points(&solution(52, 0), 52, 0, 4), 0);
+        assert_eq!(points(&solution(52, 1_999), 52, 0, 4), 0);
+        assert_eq!(points(&solution(52, 2_000), 52, 0, 4), 1);
+        assert_eq!(points(&solution(52, 10_000), 52, 0, 4), 2);
+        assert_eq!(points(&solution(52, 50_000), 52, 0, 4), 3);
+        // 88 moves is just under 1.7 a card, 89 just over.
+        assert_eq!(points(&solution(88, 0), 52, 0, 4), 0);
+        assert_eq!(points(&solution(89, 0), 52, 0, 4), 1);
+        assert_eq!(points(&solution(99, 0), 52, 0, 4), 2);
+        assert_eq!(points(&solution(110, 0), 52, 0, 4), 3);
+        assert_eq!(points(&solution(52, 0), 52, 1, 4), 0);
+        assert_eq!(points(&solution(52, 0), 52, 2, 4), 1);
+        assert_eq!(points(&solution(52, 0), 52, 3, 4), 2);
+        assert_eq!(points(&solution(52, 0), 52, 4, 4), 3);
+        assert_eq!(points(&solution(110, 50_000), 52, 4, 4), 9);
+    }
+
+    #[test]
+    fn points_do_not_count_cells_a_variant_lacks() {
+        assert_eq!(points(&solution(52, 0), 52, 0, 0), 0);
+    }
+
+    #[test]
+    fn rating_cache_survives_json() {
+        let mut cache = RatingCache::default();
+        cache.insert("freecell", 1, Some(Difficulty::Hard));
+        cache.insert("freecell", 11982, None);
+        cache.insert("penguin", 1, Some(Difficulty::Easy));
+        let json = serde_json::to_string(&cache).unwrap();
+        let cache: RatingCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(cache.get("freecell", 1), Some(Some(Difficulty::Hard)));
+        assert_eq!(cache.get("freecell", 11982), Some(None));
+        assert_eq!(cache.get("freecell", 2), None);
+        assert_eq!(cache.get("penguin", 1), Some(Some(Difficulty::Easy)));
+        assert_eq!(cache.deals("freecell", Difficulty::Hard), vec![1]);
+        assert_eq!(cache.closest("penguin", Difficulty::Expert), Some(1));
+    }
+}
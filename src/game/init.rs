@@ -1,146 +1,132 @@
 use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
-use crate::components::{Button, Cascade, Cell, Foundation};
+use crate::components::{Button, Cascade, Cell, Foundation, StatusBar};
+use crate::deal::Deal;
+use crate::theme::{self, CardStyle, Theme};
 use crate::tileset::TileSet;
-use ggez::graphics;
+use crate::variant::Variant;
 use ggez::Context;
-use nalgebra::{point, vector};
-use rand::prelude::*;
+use nalgebra::{vector, Vector2};
 use std::sync::{Arc, Mutex};
 
 const MARGIN_LENGTH: i32 = 20;
 const ICON_SIDE: i32 = 38;
+const MIN_SCREEN_WIDTH: i32 = 800;
+const SCREEN_HEIGHT: i32 = 600;
+const STATUS_HEIGHT: i32 = 36;
 
-pub fn tileset(ctx: &mut Context) -> TileSet<Option<Card>> {
-    let image = graphics::Image::new(ctx, "/cards.png").unwrap();
-    let mut tileset = TileSet::new(image, vector![CARD_WIDTH, CARD_HEIGHT]);
-    for suit in 0..4 {
-        for value in 0..13 {
-            tileset
-                .register_tile(
-                    Some(Card { suit, value }),
-                    point![value as i32, suit as i32],
-                )
-                .unwrap();
-        }
-    }
-    tileset.register_tile(None, point![12, 4]).unwrap();
-    tileset
+/// Width of the top row: foundations on the left, free cells on the right.
+fn top_row_width(variant: &Variant) -> i32 {
+    3 * MARGIN_LENGTH
+        + (variant.foundations() + variant.cells) as i32 * (CARD_WIDTH + MARGIN_LENGTH)
 }
 
-pub fn button(ctx: &mut Context) -> Button {
+/// Width of the row of cascades.
+fn cascade_row_width(variant: &Variant) -> i32 {
+    3 * MARGIN_LENGTH + variant.cascades as i32 * (CARD_WIDTH + MARGIN_LENGTH)
+}
+
+/// Horizontal offsets centering the narrower row under the wider one.
+fn row_offsets(variant: &Variant) -> (i32, i32) {
+    let top = top_row_width(variant);
+    let cascades = cascade_row_width(variant);
+    let widest = top.max(cascades);
+    ((widest - top) / 2, (widest - cascades) / 2)
+}
+
+/// The window size needed to lay out `variant`.
+pub fn screen_size(variant: &Variant) -> Vector2<i32> {
+    let width = top_row_width(variant).max(cascade_row_width(variant));
+    vector![width.max(MIN_SCREEN_WIDTH), SCREEN_HEIGHT]
+}
+
+/// The card theme called `name`, or the default one if it cannot be loaded.
+pub fn theme(ctx: &mut Context, name: &str, style: CardStyle) -> Theme {
+    theme::load(ctx, name, style).unwrap_or_else(|e| {
+        eprintln!("Could not load the {} card theme: {}", name, e);
+        theme::load(ctx, theme::DEFAULT_THEME, style).expect("The bundled card sheet is missing")
+    })
+}
+
+pub fn button(ctx: &mut Context, variant: &Variant) -> Button {
+    let (offset, _) = row_offsets(variant);
     Button::new(
         ctx,
         vector![
-            3 * MARGIN_LENGTH / 2 - ICON_SIDE / 2 + (4 * (CARD_WIDTH + MARGIN_LENGTH)),
+            offset
+                + 3 * MARGIN_LENGTH / 2
+                + (variant.foundations() as i32 * (CARD_WIDTH + MARGIN_LENGTH))
+                - ICON_SIDE / 2,
             MARGIN_LENGTH
         ],
     )
 }
 
-pub fn cascades(tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> [Cascade; 8] {
-    let mut rng = rand::thread_rng();
-    let mut deck = Card::deck();
-    deck.shuffle(&mut rng);
+pub fn status(variant: &Variant) -> StatusBar {
+    let (_, offset) = row_offsets(variant);
+    StatusBar::new(vector![
+        offset + 2 * MARGIN_LENGTH,
+        SCREEN_HEIGHT - MARGIN_LENGTH - STATUS_HEIGHT
+    ])
+}
 
-    let mut cascades = vec![
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-        Vec::new(),
-    ];
-    let mut selected_column = 0;
-    while let Some(card) = deck.pop() {
-        cascades[selected_column].push(card);
-        selected_column = (selected_column + 1) % cascades.len();
-    }
-    let mut it = cascades.into_iter().enumerate().map(|(i, cards)| {
-        Cascade::new(
-            vector![
-                2 * MARGIN_LENGTH + (i as i32 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH + CARD_HEIGHT + MARGIN_LENGTH
-            ],
-            cards,
-            tileset.clone(),
-        )
-    });
-    [
-        it.next().unwrap(),
-        it.next().unwrap(),
-        it.next().unwrap(),
-        it.next().unwrap(),
-        it.next().unwrap(),
-        it.next().unwrap(),
-        it.next().unwrap(),
-        it.next().unwrap(),
-    ]
+pub fn cascades(deal: &Deal, tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> Vec<Cascade> {
+    let (_, offset) = row_offsets(&deal.variant);
+    deal.cascades
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, cards)| {
+            Cascade::new(
+                vector![
+                    offset + 2 * MARGIN_LENGTH + (i as i32 * (CARD_WIDTH + MARGIN_LENGTH)),
+                    MARGIN_LENGTH + CARD_HEIGHT + MARGIN_LENGTH
+                ],
+                cards,
+                deal.rules(),
+                tileset.clone(),
+            )
+        })
+        .collect()
 }
-pub fn open_cells(tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> [Cell; 4] {
-    [
-        Cell::new(
-            vector![
-                3 * MARGIN_LENGTH + (4 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH
-            ],
-            None,
-            tileset.clone(),
-        ),
-        Cell::new(
-            vector![
-                3 * MARGIN_LENGTH + (5 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH
-            ],
-            None,
-            tileset.clone(),
-        ),
-        Cell::new(
-            vector![
-                3 * MARGIN_LENGTH + (6 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH
-            ],
-            None,
-            tileset.clone(),
-        ),
-        Cell::new(
-            vector![
-                3 * MARGIN_LENGTH + (7 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH
-            ],
-            None,
-            tileset,
-        ),
-    ]
+
+pub fn open_cells(deal: &Deal, tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> Vec<Cell> {
+    let (offset, _) = row_offsets(&deal.variant);
+    let first_column = deal.variant.foundations();
+    deal.cells
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, card)| {
+            Cell::new(
+                vector![
+                    offset
+                        + 3 * MARGIN_LENGTH
+                        + ((first_column + i) as i32 * (CARD_WIDTH + MARGIN_LENGTH)),
+                    MARGIN_LENGTH
+                ],
+                card,
+                tileset.clone(),
+            )
+        })
+        .collect()
 }
-pub fn foundations(tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> [Foundation; 4] {
-    [
-        Foundation::new(
-            vector![MARGIN_LENGTH, MARGIN_LENGTH],
-            vec![],
-            tileset.clone(),
-        ),
-        Foundation::new(
-            vector![MARGIN_LENGTH + (CARD_WIDTH + MARGIN_LENGTH), MARGIN_LENGTH],
-            vec![],
-            tileset.clone(),
-        ),
-        Foundation::new(
-            vector![
-                MARGIN_LENGTH + (2 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH
-            ],
-            vec![],
-            tileset.clone(),
-        ),
-        Foundation::new(
-            vector![
-                MARGIN_LENGTH + (3 * (CARD_WIDTH + MARGIN_LENGTH)),
-                MARGIN_LENGTH
-            ],
-            vec![],
-            tileset,
-        ),
-    ]
+
+pub fn foundations(deal: &Deal, tileset: Arc<Mutex<TileSet<Option<Card>>>>) -> Vec<Foundation> {
+    let (offset, _) = row_offsets(&deal.variant);
+    deal.foundations
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, cards)| {
+            Foundation::new(
+                vector![
+                    offset + MARGIN_LENGTH + (i as i32 * (CARD_WIDTH + MARGIN_LENGTH)),
+                    MARGIN_LENGTH
+                ],
+                cards,
+                deal.rules(),
+                tileset.clone(),
+            )
+        })
+        .collect()
 }
@@ -0,0 +1,381 @@
+use super::Game;
+use crate::components::{Menu, MenuEvent};
+use crate::deal::{DealId, NUMBERED_DEALS};
+use crate::settings::Entry;
+use crate::stats::{self, DealBest};
+use crate::variant::Variant;
+use ggez::event::{self, KeyCode};
+use ggez::Context;
+
+/// Longest deal number that can be typed.
+const DEAL_DIGITS: usize = 5;
+
+/// An overlay screen shown over the table, taking the input while open.
+pub enum Screen {
+    Main(Menu),
+    SelectDeal {
+        menu: Menu,
+        input: String,
+    },
+    Variant(Menu),
+    Settings(Menu),
+    Statistics(Menu),
+    /// Asking whether to abandon the game in progress for `next`.
+    Confirm {
+        menu: Menu,
+        next: NextGame,
+    },
+    /// How the game just won went.
+    Summary(Menu),
+}
+
+/// A game to start in place of the current one.
+#[derive(Clone, Copy, Debug)]
+pub enum NextGame {
+    New,
+    Restart,
+    Daily,
+    Deal(DealId),
+    Variant(Variant),
+}
+
+impl Screen {
+    pub fn menu_mut(&mut self) -> &mut Menu {
+        match self {
+            Screen::Main(menu)
+            | Screen::SelectDeal { menu, .. }
+            | Screen::Variant(menu)
+            | Screen::Settings(menu)
+            | Screen::Statistics(menu)
+            | Screen::Confirm { menu, .. }
+            | Screen::Summary(menu) => menu,
+        }
+    }
+}
+
+/// Entries of the main menu.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MainEntry {
+    NewGame,
+    SelectDeal,
+    RestartDeal,
+    Variant,
+    Options,
+    Statistics,
+    Quit,
+}
+
+impl MainEntry {
+    const ALL: [MainEntry; 7] = [
+        MainEntry::NewGame,
+        MainEntry::SelectDeal,
+        MainEntry::RestartDeal,
+        MainEntry::Variant,
+        MainEntry::Options,
+        MainEntry::Statistics,
+        MainEntry::Quit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MainEntry::NewGame => "New game",
+            MainEntry::SelectDeal => "Select deal",
+            MainEntry::RestartDeal => "Restart deal",
+            MainEntry::Variant => "Variant",
+            MainEntry::Options => "Options",
+            MainEntry::Statistics => "Statistics",
+            MainEntry::Quit => "Quit",
+        }
+    }
+}
+
+/// Entries of the summary of a game won.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SummaryEntry {
+    Replay,
+    NextDeal,
+    SaveReplay,
+}
+
+impl SummaryEntry {
+    const ALL: [SummaryEntry; 3] = [
+        SummaryEntry::Replay,
+        SummaryEntry::NextDeal,
+        SummaryEntry::SaveReplay,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SummaryEntry::Replay => "Replay this deal",
+            SummaryEntry::NextDeal => "Next deal",
+            SummaryEntry::SaveReplay => "Save replay",
+        }
+    }
+}
+
+fn labels(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|l| l.to_string()).collect()
+}
+
+impl Game {
+    pub(super) fn open_menu(&mut self) {
+        let entries: Vec<&str> = MainEntry::ALL.iter().map(MainEntry::label).collect();
+        self.screen = Some(Screen::Main(Menu::new("Freecell", &labels(&entries))));
+    }
+
+    fn open_deal_selection(&mut self) {
+        let mut menu = Menu::new("Select deal", &labels(&["Play", "Back"]));
+        menu.set_text(&[deal_prompt("")]);
+        self.screen = Some(Screen::SelectDeal {
+            menu,
+            input: String::new(),
+        });
+    }
+
+    fn open_variants(&mut self) {
+        let names: Vec<&str> = Variant::ALL.iter().map(|v| v.name).collect();
+        let mut menu = Menu::new("Variant", &labels(&names));
+        if let Some(i) = Variant::ALL.iter().position(|v| *v == self.rules.variant) {
+            menu.select(i);
+        }
+        self.screen = Some(Screen::Variant(menu));
+    }
+
+    pub(super) fn open_settings(&mut self) {
+        let menu = Menu::new(
+            "Options    (arrows to change, Escape to close)",
+            &self.settings_lines(),
+        );
+        self.screen = Some(Screen::Settings(menu));
+    }
+
+    fn open_statistics(&mut self) {
+        let stats = &self.stats;
+        let rate = (stats.won * 100).checked_div(stats.played).unwrap_or(0);
+        let mut menu = Menu::new("Statistics", &labels(&["Back"]));
+        menu.set_text(&[
+            format!("Games played: {}", stats.played),
+            format!("Games won: {} ({}%)", stats.won, rate),
+            format!("Games abandoned: {}", stats.abandoned),
+            format!("Daily challenges completed: {}", stats.dailies.len()),
+        ]);
+        self.screen = Some(Screen::Statistics(menu));
+    }
+
+    /// Show how the game just won went, along with the `previous` personal
+    /// best of its deal.
+    pub(super) fn open_summary(&mut self, previous: Option<DealBest>) {
+        let time = self.elapsed();
+        let score = self.score();
+        let deal = match (self.daily, self.deal_id) {
+            (Some(date), _) => format!("Daily challenge {}", date),
+            (None, Some(DealId::Number(number))) => format!("Deal #{}", number),
+            (None, Some(DealId::Seed(seed))) => format!("Seed {}", seed),
+            (None, None) => "Custom layout".to_string(),
+        };
+        let mut lines = vec![
+            format!("{}, {}", deal, self.rules.variant.name),
+            format!(
+                "Time: {}    Moves: {}    Score: {}",
+                stats::format_time(time),
+                self.moves,
+                score
+            ),
+            format!(
+                "Undos used: {}    Cards taken back: {}",
+                self.undos,
+                self.takebacks()
+            ),
+        ];
+        match previous {
+            Some(best) => {
+                lines.push(format!(
+                    "Best: {} moves, {}, score {}",
+                    best.moves,
+                    stats::format_time(best.time()),
+                    best.score
+                ));
+                let records: Vec<&str> = [
+                    (self.moves < best.moves, "moves"),
+                    (time.as_secs() < best.seconds, "time"),
+                    (score > best.score, "score"),
+                ]
+                .iter()
+                .filter_map(|(beaten, name)| beaten.then_some(*name))
+                .collect();
+                if !records.is_empty() {
+                    lines.push(format!("New personal best: {}!", records.join(", ")));
+                }
+            }
+            None if self.deal_id.is_some() => lines.push("First win of this deal!".to_string()),
+            None => {}
+        }
+        let entries: Vec<&str> = SummaryEntry::ALL.iter().map(SummaryEntry::label).collect();
+        let mut menu = Menu::new("You won!", &labels(&entries));
+        menu.set_text(&lines);
+        menu.select(1);
+        self.screen = Some(Screen::Summary(menu));
+    }
+
+    fn settings_lines(&self) -> Vec<String> {
+        Entry::ALL
+            .iter()
+            .map(|entry| format!("{}: {}", entry.label(), entry.value(&self.settings)))
+            .collect()
+    }
+
+    /// Change a setting, applying it at once.
+    fn change_setting(&mut self, ctx: &mut Context, entry: Entry, forward: bool) {
+        entry.change(&mut self.settings, forward);
+        if let Entry::Theme | Entry::FourColour | Entry::HighContrast = entry {
+            self.apply_theme(ctx);
+        }
+        let appearance = &self.settings.appearance;
+        self.table
+            .set_colour(appearance.background, appearance.dark);
+        let lines = self.settings_lines();
+        if let Some(screen) = &mut self.screen {
+            screen.menu_mut().set_labels(&lines);
+        }
+        self.configure_sounds();
+    }
+
+    /// Save the settings and switch to the variant chosen.
+    fn close_settings(&mut self, ctx: &mut Context) {
+        self.save_settings();
+        let variant = self.settings.variant();
+        if variant != self.rules.variant {
+            self.leave_game(ctx, NextGame::Variant(variant));
+        }
+    }
+
+    /// Deal the numbered deal typed on the deal selection screen.
+    fn select_deal(&mut self, ctx: &mut Context, input: &str) -> bool {
+        match input.parse() {
+            Ok(number) if NUMBERED_DEALS.contains(&number) => {
+                self.leave_game(ctx, NextGame::Deal(DealId::Number(number)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Start `next`, first asking whether to abandon the game in progress
+    /// if there is one and the settings say so.
+    pub(super) fn leave_game(&mut self, ctx: &mut Context, next: NextGame) {
+        if self.in_progress() && self.settings.input.confirm_abandon {
+            let mut menu = Menu::new(
+                "Abandon this game?",
+                &labels(&["Abandon it", "Keep playing"]),
+            );
+            menu.set_text(&["It will count as lost in the statistics.".to_string()]);
+            self.screen = Some(Screen::Confirm { menu, next });
+        } else {
+            self.start_next(ctx, next);
+        }
+    }
+
+    fn start_next(&mut self, ctx: &mut Context, next: NextGame) {
+        self.abandon();
+        self.search = None;
+        match next {
+            NextGame::New => self.reset(ctx),
+            NextGame::Restart => self.restart(ctx),
+            NextGame::Daily => self.start_daily(ctx),
+            NextGame::Deal(deal_id) => self.deal(ctx, deal_id),
+            NextGame::Variant(variant) => self.set_variant(ctx, variant),
+        }
+    }
+
+    pub(super) fn screen_key(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        let screen = match &mut self.screen {
+            Some(screen) => screen,
+            None => return,
+        };
+        if let Screen::SelectDeal { menu, input } = screen {
+            if keycode == KeyCode::Back {
+                input.pop();
+                menu.set_text(&[deal_prompt(input)]);
+                return;
+            }
+        }
+        if let (Screen::Settings(_), KeyCode::O) = (&screen, keycode) {
+            self.screen_event(ctx, MenuEvent::Close);
+            return;
+        }
+        if let Some(event) = screen.menu_mut().key(keycode) {
+            self.screen_event(ctx, event);
+        }
+    }
+
+    pub(super) fn screen_text(&mut self, character: char) {
+        if let Some(Screen::SelectDeal { menu, input }) = &mut self.screen {
+            if character.is_ascii_digit() && input.len() < DEAL_DIGITS {
+                input.push(character);
+                menu.set_text(&[deal_prompt(input)]);
+            }
+        }
+    }
+
+    pub(super) fn screen_event(&mut self, ctx: &mut Context, event: MenuEvent) {
+        let screen = match self.screen.take() {
+            Some(screen) => screen,
+            None => return,
+        };
+        match (screen, event) {
+            (Screen::Settings(_), MenuEvent::Close) => self.close_settings(ctx),
+            (_, MenuEvent::Close) => {}
+            (Screen::Main(_), MenuEvent::Activate(i)) => match MainEntry::ALL[i] {
+                MainEntry::NewGame => self.leave_game(ctx, NextGame::New),
+                MainEntry::SelectDeal => self.open_deal_selection(),
+                MainEntry::RestartDeal => self.leave_game(ctx, NextGame::Restart),
+                MainEntry::Variant => self.open_variants(),
+                MainEntry::Options => self.open_settings(),
+                MainEntry::Statistics => self.open_statistics(),
+                MainEntry::Quit => event::quit(ctx),
+            },
+            (Screen::SelectDeal { menu, input }, MenuEvent::Activate(0)) => {
+                if !self.select_deal(ctx, &input) {
+                    let mut menu = menu;
+                    menu.set_text(&[
+                        deal_prompt(&input),
+                        format!(
+                            "Deals are numbered from {} to {}",
+                            NUMBERED_DEALS.start(),
+                            NUMBERED_DEALS.end()
+                        ),
+                    ]);
+                    self.screen = Some(Screen::SelectDeal { menu, input });
+                }
+            }
+            (Screen::SelectDeal { .. }, MenuEvent::Activate(_)) => self.open_menu(),
+            (Screen::Variant(_), MenuEvent::Activate(i)) => {
+                self.leave_game(ctx, NextGame::Variant(Variant::ALL[i]));
+            }
+            (Screen::Confirm { next, .. }, MenuEvent::Activate(0)) => self.start_next(ctx, next),
+            (Screen::Confirm { .. }, MenuEvent::Activate(_)) => {}
+            (Screen::Settings(menu), MenuEvent::Activate(i)) => {
+                self.screen = Some(Screen::Settings(menu));
+                self.change_setting(ctx, Entry::ALL[i], true);
+            }
+            (Screen::Settings(menu), MenuEvent::Change(i, forward)) => {
+                self.screen = Some(Screen::Settings(menu));
+                self.change_setting(ctx, Entry::ALL[i], forward);
+            }
+            (Screen::Statistics(_), MenuEvent::Activate(_)) => self.open_menu(),
+            (Screen::Summary(menu), MenuEvent::Activate(i)) => match SummaryEntry::ALL[i] {
+                SummaryEntry::Replay => self.leave_game(ctx, NextGame::Restart),
+                SummaryEntry::NextDeal => self.leave_game(ctx, NextGame::New),
+                SummaryEntry::SaveReplay => {
+                    self.save_replay();
+                    self.screen = Some(Screen::Summary(menu));
+                }
+            },
+            (screen, MenuEvent::Change(..)) => self.screen = Some(screen),
+        }
+    }
+}
+
+fn deal_prompt(input: &str) -> String {
+    format!("Deal number: {}_", input)
+}
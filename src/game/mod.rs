@@ -1,36 +1,110 @@
+use crate::board::{Board, Move, Pile};
 use crate::card::Card;
-use crate::components::{Button, Cascade, Cell, Finale, Foundation, Hand};
+use crate::components::{
+    table_position, Button, Cascade, Celebration, Cell, Fill, Finale, Foundation, Hand, Highlight,
+    StatusBar, Table,
+};
+use crate::daily::Date;
+use crate::deal::{Deal, DealId};
+use crate::difficulty::Difficulty;
+use crate::notation;
+use crate::save::SavedGame;
+use crate::search::{self, Search};
+use crate::settings::{AutoPlay, InputMode, Settings};
+use crate::solver;
+use crate::sound::{Effect, Sounds};
+use crate::stats::{self, DailyRecord, DealBest, Stats};
+use crate::storage;
+use crate::theme;
 use crate::tileset::TileSet;
-use ggez::audio::{SoundData, SoundSource, Source};
-use ggez::event::EventHandler;
-use ggez::graphics::{self, Color};
-use ggez::input::mouse::MouseButton;
+use crate::variant::{Rules, Takeback, Variant, TAKEBACK_PENALTY};
+use ggez::event::{EventHandler, KeyCode, KeyMods};
+use ggez::graphics::{self, Image, Rect};
+use ggez::input::mouse::{self, CursorIcon, MouseButton};
 use ggez::{Context, GameResult};
-use nalgebra::{vector, Vector2};
+use nalgebra::Vector2;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 mod init;
+mod menus;
+
+use menus::{NextGame, Screen};
 
 pub trait Collision {
     fn inside(&self, pos: Vector2<i32>) -> bool;
 }
 
-enum CardSource {
-    Cell(usize),
-    Cascade(usize),
-    Foundation(usize),
+/// Points earned for each card on the foundations.
+const FOUNDATION_CARD_SCORE: i32 = 10;
+const SAVE_FILE: &str = "save.json";
+/// Directory of the replays of games won, in the user data directory.
+const REPLAYS_DIR: &str = "replays";
+
+/// Moves, penalty and time already spent on a resumed game.
+pub struct Progress {
+    pub moves: u32,
+    pub penalty: i32,
+    pub elapsed: Duration,
+}
+
+/// How the first game is set up.
+pub struct Start {
+    pub deal: Deal,
+    pub deal_id: Option<DealId>,
+    pub progress: Option<Progress>,
+    /// A window size to keep, rather than fitting the window to the table.
+    pub window_size: Option<(f32, f32)>,
+    pub muted: bool,
+    /// Whether there is an audio device to play sounds on.
+    pub audio: bool,
 }
 
 pub struct Game {
+    rules: Rules,
+    /// Whether cards can be taken back from the foundations, fixed when the
+    /// current game was dealt.
+    takeback: Takeback,
+    /// How the current game was dealt, if it can be dealt again.
+    deal_id: Option<DealId>,
+    /// The layout the current game started from, to restart it.
+    opening: Deal,
+    settings: Settings,
+    /// The menu or other screen shown over the table, if any.
+    screen: Option<Screen>,
+    search: Option<Search>,
+    /// The last deal found winnable for each variant, by id.
+    solvable_deals: HashMap<&'static str, DealId>,
+    penalty: i32,
+    moves: u32,
+    /// The moves of the current attempt, missing those made before it was
+    /// saved if it was resumed.
+    history: Vec<Move>,
+    /// Moves of the current attempt taken back with the undo key.
+    undos: u32,
+    started: Instant,
+    /// How long the current game took to win, once recorded.
+    finished: Option<Duration>,
+    /// The date of the daily challenge being played, if any.
+    daily: Option<Date>,
+    stats: Stats,
+    window_size: Option<(f32, f32)>,
+    /// Silenced from the command line, whatever the settings.
+    muted: bool,
     tileset: Arc<Mutex<TileSet<Option<Card>>>>,
-    cascades: [Cascade; 8],
-    open_cells: [Cell; 4],
-    foundations: [Foundation; 4],
+    table: Table,
+    cascades: Vec<Cascade>,
+    open_cells: Vec<Cell>,
+    foundations: Vec<Foundation>,
     hand: Hand,
-    hand_card_source: Option<CardSource>,
+    hand_card_source: Option<Pile>,
     button: Button,
+    status: StatusBar,
     finale: Finale,
-    deal_audio: SoundData,
+    sounds: Arc<Mutex<Sounds>>,
 }
 
 impl Game {
@@ -38,6 +112,167 @@ impl Game {
         self.foundations.iter().all(|f| f.is_full())
     }
 
+    fn score(&self) -> i32 {
+        let cards: usize = self.foundations.iter().map(|f| f.len()).sum();
+        cards as i32 * FOUNDATION_CARD_SCORE - self.penalty
+    }
+
+    /// Settle the cards in hand being put on `target`, counting the move and
+    /// any card taken back from a foundation.
+    fn settle_drop(&mut self, target: Pile) {
+        let source = match self.hand_card_source {
+            Some(source) if source != target => source,
+            _ => return,
+        };
+        self.moves += 1;
+        self.history.push(Move {
+            from: source,
+            to: target,
+            count: self.hand.len(),
+        });
+        if let (Some(Pile::Foundation(_)), Pile::Cascade(_) | Pile::Cell(_)) =
+            (self.hand_card_source, target)
+        {
+            if self.takeback == Takeback::Penalized {
+                self.penalty += TAKEBACK_PENALTY;
+            }
+        }
+    }
+
+    /// Start counting moves and time for a new attempt.
+    fn start_attempt(&mut self) {
+        self.moves = 0;
+        self.history.clear();
+        self.undos = 0;
+        self.started = Instant::now();
+        self.finished = None;
+        self.stats.played += 1;
+        self.save_stats();
+    }
+
+    fn save_stats(&mut self) {
+        if let Err(e) = self.stats.save() {
+            eprintln!("Could not save statistics: {}", e);
+        }
+    }
+
+    /// Whether the current game has been played without being won yet.
+    fn in_progress(&self) -> bool {
+        self.moves > 0 && self.finished.is_none()
+    }
+
+    /// Record the current game as lost if it was in progress.
+    fn abandon(&mut self) {
+        if self.in_progress() {
+            self.stats.abandoned += 1;
+            self.save_stats();
+            // Not to count it twice while another deal is searched for.
+            self.moves = 0;
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    /// Record the victory of the current game, returning the personal best
+    /// of its deal before it.
+    fn finish(&mut self) -> Option<DealBest> {
+        let elapsed = self.started.elapsed();
+        self.finished = Some(elapsed);
+        self.stats.won += 1;
+        if let (Some(date), Some(DealId::Number(deal))) = (self.daily, self.deal_id) {
+            self.stats.record_daily(DailyRecord {
+                date,
+                deal,
+                moves: self.moves,
+                seconds: elapsed.as_secs(),
+            });
+        }
+        let mut previous = None;
+        if let Some(deal) = self.deal_id {
+            let variant = self.rules.variant.id;
+            previous = self.stats.best(variant, deal).cloned();
+            self.stats.record_best(DealBest {
+                variant: variant.to_string(),
+                deal,
+                moves: self.moves,
+                seconds: elapsed.as_secs(),
+                score: self.score(),
+            });
+        }
+        self.save_stats();
+        previous
+    }
+
+    /// The number of cards taken back from the foundations.
+    fn takebacks(&self) -> usize {
+        self.history
+            .iter()
+            .filter(|m| {
+                matches!(m.from, Pile::Foundation(_)) && !matches!(m.to, Pile::Foundation(_))
+            })
+            .count()
+    }
+
+    /// Take back the last move of the current attempt. Cards leaving the
+    /// foundations this way follow the takeback rule.
+    fn undo(&mut self, ctx: &mut Context) {
+        let m = match self.history.last() {
+            Some(m) => *m,
+            None => {
+                self.status.notify("No move to undo");
+                return;
+            }
+        };
+        if let Pile::Foundation(_) = m.to {
+            match self.takeback {
+                Takeback::Forbidden => {
+                    self.status
+                        .notify("Cards cannot be taken back from the foundations");
+                    return;
+                }
+                Takeback::Penalized => self.penalty += TAKEBACK_PENALTY,
+                Takeback::Allowed => {}
+            }
+        }
+        let mut cards = match m.to {
+            Pile::Cascade(i) => self.cascades[i].take(m.count),
+            Pile::Cell(i) => self.open_cells[i].take().into_iter().collect(),
+            Pile::Foundation(i) => self.foundations[i].take().into_iter().collect(),
+        };
+        match m.from {
+            Pile::Cascade(i) => self.cascades[i].put(cards),
+            Pile::Cell(i) => self.open_cells[i].put(cards.pop().unwrap()),
+            Pile::Foundation(i) => self.foundations[i].put(cards.pop().unwrap()),
+        }
+        self.history.pop();
+        self.moves -= 1;
+        self.undos += 1;
+        self.play(ctx, Effect::Drop);
+    }
+
+    /// Start the celebration chosen in the settings.
+    fn celebrate(&mut self, ctx: &mut Context) {
+        let foundations: Vec<_> = self
+            .foundations
+            .iter()
+            .map(|f| (f.pos(), f.cards().to_vec()))
+            .collect();
+        let celebration = self.settings.appearance.celebration;
+        // Made now rather than when dealing, for the volume to be current.
+        let audio = match celebration {
+            Celebration::None => None,
+            _ => self.sounds.lock().unwrap().source(ctx, Effect::Finale),
+        };
+        self.finale
+            .start(celebration, &foundations, self.moves, audio);
+        if celebration != Celebration::Panel {
+            self.status
+                .notify("You won! Click or press Space for a new deal");
+        }
+    }
+
     fn empty_cell_count(&self) -> usize {
         self.open_cells.iter().filter(|c| c.is_empty()).count()
     }
@@ -46,104 +281,671 @@ impl Game {
         self.cascades.iter().filter(|c| c.is_empty()).count()
     }
 
-    pub fn new(ctx: &mut Context) -> Self {
-        let tileset = Arc::new(Mutex::new(init::tileset(ctx)));
-        let cascades = init::cascades(tileset.clone());
-        let open_cells = init::open_cells(tileset.clone());
-        let foundations = init::foundations(tileset.clone());
-        let hand = Hand::new(ctx, tileset.clone());
-        let deal_audio = SoundData::new(ctx, "/deal.wav").unwrap();
-        let button = init::button(ctx);
+    pub fn new(ctx: &mut Context, start: Start, settings: Settings) -> Self {
+        let Start {
+            deal,
+            deal_id,
+            progress,
+            window_size,
+            muted,
+            audio,
+        } = start;
+        let variant = deal.variant;
+        // A resumed game restarts from its deal rather than where it was saved.
+        let opening = match (&progress, deal_id) {
+            (Some(_), Some(deal_id)) => Deal::from_id(variant, deal_id),
+            _ => deal.clone(),
+        };
+        let theme = init::theme(
+            ctx,
+            &settings.appearance.theme,
+            settings.appearance.card_style(),
+        );
+        let tileset = Arc::new(Mutex::new(theme.tiles));
+        let appearance = &settings.appearance;
+        let table = Table::new(appearance.background, appearance.dark);
+        let cascades = init::cascades(&deal, tileset.clone());
+        let open_cells = init::open_cells(&deal, tileset.clone());
+        let foundations = init::foundations(&deal, tileset.clone());
+        let mut sounds = Sounds::new(ctx, &settings.audio, audio);
+        sounds.configure(&settings.audio, muted);
+        let finale = Finale::new(tileset.clone());
+        let sounds = Arc::new(Mutex::new(sounds));
+        let hand = Hand::new(tileset.clone(), sounds.clone());
+        let button = init::button(ctx, &variant);
+        let status = init::status(&variant);
 
-        let game = Self {
+        let mut game = Self {
+            rules: deal.rules(),
+            takeback: settings.rules.takeback,
+            deal_id,
+            opening,
+            settings,
+            screen: None,
+            search: None,
+            solvable_deals: HashMap::new(),
+            penalty: 0,
+            moves: 0,
+            history: Vec::new(),
+            undos: 0,
+            started: Instant::now(),
+            finished: None,
+            daily: None,
+            stats: Stats::load(),
+            window_size,
+            muted,
             cascades,
             open_cells,
             foundations,
             hand,
             hand_card_source: None,
-            finale: Finale::new(ctx, tileset.clone()),
+            finale,
             tileset,
-            deal_audio,
+            table,
+            sounds,
             button,
+            status,
         };
-        game.play_deal(ctx);
+        game.set_table_image(ctx, theme.table);
+        game.fit_window(ctx);
+        game.play(ctx, Effect::Deal);
+        match progress {
+            Some(progress) => {
+                game.moves = progress.moves;
+                game.penalty = progress.penalty;
+                game.started = Instant::now()
+                    .checked_sub(progress.elapsed)
+                    .unwrap_or_else(Instant::now);
+            }
+            None => game.start_attempt(),
+        }
         game
     }
 
-    fn play_send(&self, ctx: &mut Context) {
-        let mut source = Source::from_data(ctx, self.deal_audio.clone()).unwrap();
-        source.set_volume(0.2);
-        source.play_detached(ctx).unwrap();
+    /// Why the cards in hand cannot be put on `target`, if they cannot.
+    fn refusal(&self, target: Pile) -> Option<String> {
+        let card = self.hand.top_card()?;
+        let count = self.hand.len();
+        match target {
+            Pile::Cascade(i) => {
+                let cascade = &self.cascades[i];
+                // Neither the cascade the cards are put on nor the one they
+                // were all taken from can hold them on the way.
+                let free_cascades = (0..self.cascades.len())
+                    .map(Pile::Cascade)
+                    .filter(|pile| *pile != target && Some(*pile) != self.hand_card_source)
+                    .filter(|pile| matches!(pile, Pile::Cascade(j) if self.cascades[*j].is_empty()))
+                    .count();
+                let movable = self
+                    .rules
+                    .movable_cards(self.empty_cell_count(), free_cascades);
+                match cascade.bottom_card() {
+                    _ if count > movable => {
+                        Some(format!("Not enough free cells to move {} cards", count))
+                    }
+                    _ if cascade.can_stack(card) => None,
+                    Some(bottom) => Some(format!("{} cannot go on {}", card, bottom)),
+                    None => Some(self.rules.empty_cascade_rule()),
+                }
+            }
+            Pile::Cell(_) if count > 1 => Some("Only one card fits in a free cell".to_string()),
+            Pile::Cell(i) if !self.open_cells[i].is_empty() => {
+                Some("That free cell is taken".to_string())
+            }
+            Pile::Cell(_) => None,
+            Pile::Foundation(_) if count > 1 => {
+                Some("Cards go to the foundations one at a time".to_string())
+            }
+            Pile::Foundation(i) => {
+                let foundation = &self.foundations[i];
+                match foundation.top_card() {
+                    _ if foundation.can_stack(card) => None,
+                    Some(top) => Some(format!("{} cannot go on {}", card, top)),
+                    None => Some(format!("{} cannot start a foundation", card)),
+                }
+            }
+        }
     }
 
-    fn play_deal(&self, ctx: &mut Context) {
-        let mut source = Source::from_data(ctx, self.deal_audio.clone()).unwrap();
-        source.set_volume(2.);
-        source.set_pitch(2.);
-        source.play_detached(ctx).unwrap();
+    /// Whether the cards in hand can be put on `target`.
+    fn accepts(&self, target: Pile) -> bool {
+        !self.hand.is_empty() && self.refusal(target).is_none()
     }
 
+    /// Every pile of the table, in the order drops are looked for.
+    fn piles(&self) -> Vec<Pile> {
+        let cascades = (0..self.cascades.len()).map(Pile::Cascade);
+        let cells = (0..self.open_cells.len()).map(Pile::Cell);
+        let foundations = (0..self.foundations.len()).map(Pile::Foundation);
+        cascades.chain(cells).chain(foundations).collect()
+    }
+
+    fn bounds(&self, pile: Pile) -> Rect {
+        match pile {
+            Pile::Cascade(i) => self.cascades[i].bounds(),
+            Pile::Cell(i) => self.open_cells[i].bounds(),
+            Pile::Foundation(i) => self.foundations[i].bounds(),
+        }
+    }
+
+    /// The pile the cards in hand cover the most, among the one they came
+    /// from and those `allowed`.
+    fn most_covered(&self, allowed: impl Fn(Pile) -> bool) -> Option<Pile> {
+        let hand = self.hand.bounds();
+        self.piles()
+            .into_iter()
+            .filter(|pile| Some(*pile) == self.hand_card_source || allowed(*pile))
+            .map(|pile| (pile, overlap(hand, self.bounds(pile))))
+            .filter(|(_, area)| *area > 0.)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(pile, _)| pile)
+    }
+
+    /// How many cards of cascade `i` would be picked up at `pos`, and whether
+    /// they can be: built down in sequence and few enough to be moved at
+    /// once.
+    fn run_at(&self, i: usize, pos: Vector2<i32>) -> (usize, bool) {
+        let cascade = &self.cascades[i];
+        let count = cascade.cards_to_take(pos);
+        let movable = self
+            .rules
+            .movable_cards(self.empty_cell_count(), self.empty_cascade_count());
+        (
+            count,
+            count > 0 && count <= movable && cascade.has_sequence(count),
+        )
+    }
+
+    /// Tint the cards under the cursor after whether they can be picked up,
+    /// showing a forbidding cursor if they cannot.
+    fn hover_cascades(&mut self, ctx: &mut Context) {
+        let cursor = mouse::position(ctx);
+        let pos = table_position(ctx, cursor.x, cursor.y);
+        let idle = self.hand.is_empty()
+            && self.screen.is_none()
+            && self.search.is_none()
+            && !self.is_victory();
+        let mut stuck = false;
+        for i in 0..self.cascades.len() {
+            let (count, movable) = if idle && self.cascades[i].inside(pos) {
+                self.run_at(i, pos)
+            } else {
+                (0, false)
+            };
+            let highlight = if movable {
+                Highlight::Movable
+            } else {
+                stuck |= count > 0;
+                Highlight::Stuck
+            };
+            self.cascades[i].set_hover(count, highlight);
+        }
+        let icon = if stuck {
+            CursorIcon::NotAllowed
+        } else {
+            CursorIcon::Default
+        };
+        if mouse::cursor_type(ctx) != icon {
+            mouse::set_cursor_type(ctx, icon);
+        }
+    }
+
+    /// Show which piles would take the cards in hand, if any are held.
+    fn highlight_targets(&mut self) {
+        for pile in self.piles() {
+            let highlight = if self.hand.is_empty()
+                || self.hand.is_returning()
+                || self.hand_card_source == Some(pile)
+            {
+                Highlight::None
+            } else if self.accepts(pile) {
+                Highlight::Legal
+            } else {
+                Highlight::Illegal
+            };
+            match pile {
+                Pile::Cascade(i) => self.cascades[i].set_highlight(highlight),
+                Pile::Cell(i) => self.open_cells[i].set_highlight(highlight),
+                Pile::Foundation(i) => self.foundations[i].set_highlight(highlight),
+            }
+        }
+    }
+
+    /// Put the cards in hand, held at `pos`, on the pile they cover the most
+    /// among those that take them and the one they came from, or send them
+    /// flying back where they came from if there is none.
+    fn drop_hand(&mut self, ctx: &mut Context, pos: Vector2<i32>) {
+        if self.hand.is_empty() || self.hand.is_returning() {
+            return;
+        }
+        self.hand.hold_at(pos);
+        let target = self.most_covered(|pile| self.accepts(pile));
+        if target.is_some() && target == self.hand_card_source {
+            self.return_hand(ctx);
+            return;
+        }
+        if let Some(target) = target {
+            self.settle_drop(target);
+            match target {
+                Pile::Cascade(i) => self.cascades[i].put(self.hand.take(ctx)),
+                Pile::Cell(i) => self.open_cells[i].put(self.hand.take(ctx).pop().unwrap()),
+                Pile::Foundation(i) => self.foundations[i].put(self.hand.take(ctx).pop().unwrap()),
+            }
+            self.hand_card_source = None;
+            self.auto_play(ctx);
+            return;
+        }
+        // Tell why when put on another pile.
+        let refused = self.most_covered(|_| true);
+        if let Some(reason) = refused.and_then(|pile| self.refusal(pile)) {
+            self.status.notify(&reason);
+            self.play(ctx, Effect::Illegal);
+        }
+        let origin = match self.hand_card_source {
+            Some(Pile::Cascade(n)) => self.cascades[n].next_pos(),
+            Some(Pile::Cell(n)) => self.open_cells[n].pos(),
+            Some(Pile::Foundation(n)) => self.foundations[n].pos(),
+            None => return,
+        };
+        self.hand.send_back(origin);
+    }
+
+    /// Put the cards flown back where they came from.
+    fn return_hand(&mut self, ctx: &mut Context) {
+        match self.hand_card_source {
+            Some(Pile::Cell(n)) => {
+                self.open_cells[n].put(self.hand.take(ctx).pop().unwrap());
+            }
+            Some(Pile::Cascade(n)) => {
+                self.cascades[n].put(self.hand.take(ctx));
+            }
+            Some(Pile::Foundation(n)) => {
+                self.foundations[n].put(self.hand.take(ctx).pop().unwrap());
+            }
+            None => return,
+        }
+        self.hand_card_source = None;
+    }
+
+    /// Resize the window to the layout of the current variant, or scale the
+    /// table to fit a window of the size asked for.
+    fn fit_window(&self, ctx: &mut Context) {
+        let size = init::screen_size(&self.rules.variant);
+        let (width, height) = (size.x as f32, size.y as f32);
+        match self.window_size {
+            Some((window_width, window_height)) => {
+                graphics::set_drawable_size(ctx, window_width, window_height).unwrap();
+                let scale = (width / window_width).max(height / window_height);
+                let screen = Rect::new(0., 0., window_width * scale, window_height * scale);
+                graphics::set_screen_coordinates(ctx, screen).unwrap();
+            }
+            None => {
+                graphics::set_drawable_size(ctx, width, height).unwrap();
+                graphics::set_screen_coordinates(ctx, Rect::new(0., 0., width, height)).unwrap();
+            }
+        }
+    }
+
+    /// The current position as plain data.
+    fn board(&self) -> Board {
+        Board {
+            rules: self.rules,
+            cascades: self.cascades.iter().map(|c| c.cards().to_vec()).collect(),
+            cells: self.open_cells.iter().map(|c| c.card().cloned()).collect(),
+            foundations: self
+                .foundations
+                .iter()
+                .map(|f| f.cards().to_vec())
+                .collect(),
+        }
+    }
+
+    fn save_game(&mut self) {
+        let board = self.board();
+        let saved = SavedGame {
+            variant: self.rules.variant.id.to_string(),
+            deal: self.deal_id,
+            base_rank: self.rules.base_rank,
+            cascades: board.cascades,
+            cells: board.cells,
+            foundations: board.foundations,
+            moves: self.moves,
+            penalty: self.penalty,
+            seconds: self.elapsed().as_secs(),
+        };
+        let result = match storage::data_file(SAVE_FILE) {
+            Some(path) => saved.save(&path).map(|_| path),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No home directory",
+            )),
+        };
+        match result {
+            Ok(path) => self
+                .status
+                .notify(&format!("Game saved to {}", path.display())),
+            Err(e) => self
+                .status
+                .notify(&format!("Could not save the game: {}", e)),
+        }
+    }
+
+    /// Write the moves of the game won to the replays directory, to be
+    /// played again with the `replay` command.
+    fn save_replay(&mut self) {
+        if self.history.len() != self.moves as usize {
+            self.status
+                .notify("The moves made before the game was saved are missing");
+            return;
+        }
+        let mut board = Board::from(&self.opening);
+        let mut text = String::new();
+        for m in self.history.iter() {
+            text += &notation::format_move(&board, m);
+            text.push('\n');
+            board.apply(m);
+        }
+        let deal = match self.deal_id {
+            Some(DealId::Number(number)) => number.to_string(),
+            Some(DealId::Seed(seed)) => format!("seed-{}", seed),
+            None => "layout".to_string(),
+        };
+        let name = format!("{}-{}.txt", self.rules.variant.id, deal);
+        let result = match storage::data_file(REPLAYS_DIR) {
+            Some(dir) => fs::create_dir_all(&dir)
+                .and_then(|_| fs::write(dir.join(&name), text))
+                .map(|_| dir.join(name)),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No home directory",
+            )),
+        };
+        match result {
+            Ok(path) => self
+                .status
+                .notify(&format!("Replay saved to {}", path.display())),
+            Err(e) => self
+                .status
+                .notify(&format!("Could not save the replay: {}", e)),
+        }
+    }
+
+    /// Draw the cards with the theme set, keeping the current one if it
+    /// cannot be loaded.
+    fn apply_theme(&mut self, ctx: &mut Context) {
+        let appearance = &self.settings.appearance;
+        let name = &appearance.theme;
+        match theme::load(ctx, name, appearance.card_style()) {
+            Ok(theme) => {
+                *self.tileset.lock().unwrap() = theme.tiles;
+                self.set_table_image(ctx, theme.table);
+            }
+            Err(e) => self
+                .status
+                .notify(&format!("Could not load the {} card theme: {}", name, e)),
+        }
+    }
+
+    /// Cover the table with the image set in the settings, or else with
+    /// `theme_image`.
+    fn set_table_image(&mut self, ctx: &mut Context, theme_image: Option<(Image, Fill)>) {
+        let appearance = &self.settings.appearance;
+        let image = match &appearance.image {
+            Some(path) => match theme::table_image(ctx, path) {
+                Ok(image) => Some((image, appearance.fill)),
+                Err(e) => {
+                    eprintln!("Could not load the table image: {}", e);
+                    theme_image
+                }
+            },
+            None => theme_image,
+        };
+        self.table.set_image(image);
+    }
+
+    /// Apply the audio settings to the sounds.
+    fn configure_sounds(&mut self) {
+        let mut sounds = self.sounds.lock().unwrap();
+        sounds.configure(&self.settings.audio, self.muted);
+        self.finale.set_volume(sounds.volume(Effect::Finale));
+    }
+
+    /// Turn the sound off or back on, which lifts `--mute` too.
+    fn toggle_mute(&mut self) {
+        let muted = !(self.muted || self.settings.audio.muted);
+        self.muted = false;
+        self.settings.audio.muted = muted;
+        self.configure_sounds();
+        self.save_settings();
+        self.status
+            .notify(if muted { "Sound off" } else { "Sound on" });
+    }
+
+    fn save_settings(&mut self) {
+        if let Err(e) = self.settings.save() {
+            self.status
+                .notify(&format!("Could not save the settings: {}", e));
+        }
+    }
+
+    /// Send the cards which are safe to send to the foundations, if asked to
+    /// in the settings.
+    fn auto_play(&mut self, ctx: &mut Context) {
+        if self.settings.input.auto_play == AutoPlay::Off {
+            return;
+        }
+        let moves = solver::auto_play(&mut self.board());
+        for m in moves.iter() {
+            let card = match m.from {
+                Pile::Cascade(i) => self.cascades[i].take(1).pop(),
+                Pile::Cell(i) => self.open_cells[i].take(),
+                Pile::Foundation(_) => None,
+            };
+            if let (Some(card), Pile::Foundation(f)) = (card, m.to) {
+                self.foundations[f].put(card);
+                self.moves += 1;
+                self.history.push(*m);
+            }
+        }
+        if !moves.is_empty() {
+            self.play(ctx, Effect::Send);
+        }
+    }
+
+    fn play(&self, ctx: &mut Context, effect: Effect) {
+        self.sounds.lock().unwrap().play(ctx, effect);
+    }
+
+    /// Deal a new game, looking for one of the requested difficulty or a
+    /// winnable one first if asked to.
     fn reset(&mut self, ctx: &mut Context) {
-        self.play_deal(ctx);
-        self.cascades = init::cascades(self.tileset.clone());
-        self.open_cells = init::open_cells(self.tileset.clone());
-        self.foundations = init::foundations(self.tileset.clone());
-        self.hand = Hand::new(ctx, self.tileset.clone());
-        self.finale = Finale::new(ctx, self.tileset.clone());
+        let variant = self.rules.variant;
+        if let Some(difficulty) = self.settings.rules.difficulty {
+            self.search = Some(Search::rated(variant, difficulty, search::SEARCH_BUDGET));
+        } else if self.settings.rules.winnable_only {
+            let fallback = *self
+                .solvable_deals
+                .get(variant.id)
+                .unwrap_or(&DealId::Seed(variant.solvable_seed));
+            self.search = Some(Search::winnable(variant, fallback, search::SEARCH_BUDGET));
+        } else {
+            self.deal(ctx, DealId::Seed(rand::thread_rng().gen()));
+        }
+    }
+
+    fn deal(&mut self, ctx: &mut Context, deal_id: DealId) {
+        let deal = Deal::from_id(self.rules.variant, deal_id);
+        self.deal_id = Some(deal_id);
+        self.daily = None;
+        self.lay_out(ctx, deal);
+    }
+
+    /// Put the cards of `deal` on the table and start a new attempt at it.
+    fn lay_out(&mut self, ctx: &mut Context, deal: Deal) {
+        self.play(ctx, Effect::Deal);
+        self.rules = deal.rules();
+        self.takeback = self.settings.rules.takeback;
+        self.penalty = 0;
+        self.cascades = init::cascades(&deal, self.tileset.clone());
+        self.open_cells = init::open_cells(&deal, self.tileset.clone());
+        self.foundations = init::foundations(&deal, self.tileset.clone());
+        self.hand = Hand::new(self.tileset.clone(), self.sounds.clone());
+        self.hand_card_source = None;
+        self.finale = Finale::new(self.tileset.clone());
+        self.opening = deal;
+        self.start_attempt();
+    }
+
+    /// Put the cards back as they were dealt, keeping the deal and any daily
+    /// challenge, as a new attempt.
+    fn restart(&mut self, ctx: &mut Context) {
+        self.search = None;
+        self.lay_out(ctx, self.opening.clone());
+        self.status.notify("Deal restarted");
+    }
+
+    /// Lay out the table for `variant`, without dealing.
+    fn use_variant(&mut self, ctx: &mut Context, variant: Variant) {
+        self.rules.variant = variant;
+        self.button = init::button(ctx, &variant);
+        self.status = init::status(&variant);
+        self.fit_window(ctx);
+    }
+
+    /// Switch to `variant` and deal a new game with it, remembering it for
+    /// the next time the game starts.
+    fn set_variant(&mut self, ctx: &mut Context, variant: Variant) {
+        self.use_variant(ctx, variant);
+        self.settings.rules.variant = variant.id.to_string();
+        self.save_settings();
+        self.reset(ctx);
+    }
+
+    /// Deal today's challenge, a classic Freecell deal numbered from the date.
+    fn start_daily(&mut self, ctx: &mut Context) {
+        if self.rules.variant != Variant::FREECELL {
+            self.use_variant(ctx, Variant::FREECELL);
+        }
+        let date = Date::today();
+        self.deal(ctx, DealId::Number(date.deal_number()));
+        self.daily = Some(date);
+    }
+
+    /// The first part of the status line, naming the deal.
+    fn deal_label(&self) -> String {
+        match (self.daily, self.deal_id) {
+            (Some(date), _) => match self.stats.daily(date) {
+                Some(record) => format!(
+                    "Daily challenge {} (completed in {} moves, {})    ",
+                    date,
+                    record.moves,
+                    stats::format_time(record.time())
+                ),
+                None => format!("Daily challenge {}    ", date),
+            },
+            (None, Some(DealId::Number(number))) => format!("Deal #{}    ", number),
+            (None, _) => String::new(),
+        }
+    }
+}
+
+/// The area `a` and `b` have in common.
+fn overlap(a: Rect, b: Rect) -> f32 {
+    let width = a.right().min(b.right()) - a.left().max(b.left());
+    let height = a.bottom().min(b.bottom()) - a.top().max(b.top());
+    width.max(0.) * height.max(0.)
+}
+
+/// `difficulty` with its indefinite article, as in "an easy".
+fn article(difficulty: Difficulty) -> String {
+    match difficulty {
+        Difficulty::Easy | Difficulty::Expert => format!("an {}", difficulty),
+        Difficulty::Medium | Difficulty::Hard => format!("a {}", difficulty),
     }
 }
 
 impl EventHandler<ggez::GameError> for Game {
-    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
-        if self.is_victory() {
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymods: KeyMods,
+        _repeat: bool,
+    ) {
+        if self.screen.is_some() {
+            self.screen_key(ctx, keycode);
             return;
         }
-        if button == MouseButton::Left {
-            let pos = vector![x as i32, y as i32];
-            if !self.hand.is_empty() {
-                for c in self.cascades.iter_mut() {
-                    if c.inside(pos) && c.can_stack(self.hand.top_card().unwrap()) {
-                        c.put(self.hand.take(ctx));
-                        return;
-                    }
-                }
-
-                for c in self.open_cells.iter_mut() {
-                    if c.inside(pos) && self.hand.is_single_card() && c.is_empty() {
-                        c.put(self.hand.take(ctx).pop().unwrap());
-                        return;
-                    }
-                }
-                for f in self.foundations.iter_mut() {
-                    if f.inside(pos)
-                        && self.hand.is_single_card()
-                        && f.can_stack(self.hand.top_card().unwrap())
-                    {
-                        f.put(self.hand.take(ctx).pop().unwrap());
-                        return;
-                    }
-                }
-                // return cards back if they're not put anywhere
-                match self.hand_card_source {
-                    Some(CardSource::Cell(n)) => {
-                        self.open_cells[n].put(self.hand.take(ctx).pop().unwrap());
-                    }
-                    Some(CardSource::Cascade(n)) => {
-                        self.cascades[n].put(self.hand.take(ctx));
-                    }
-                    Some(CardSource::Foundation(n)) => {
-                        self.foundations[n].put(self.hand.take(ctx).pop().unwrap());
-                    }
-                    None => {
-                        panic!("No card source");
-                    }
-                }
-                self.hand_card_source = None;
+        if self.is_victory() && matches!(keycode, KeyCode::Space | KeyCode::Return) {
+            self.reset(ctx);
+            return;
+        }
+        let rules = &mut self.settings.rules;
+        match keycode {
+            KeyCode::Escape if self.hand.is_empty() => self.open_menu(),
+            KeyCode::O if self.hand.is_empty() => self.open_settings(),
+            KeyCode::M => self.toggle_mute(),
+            _ if self.search.is_some() => {}
+            KeyCode::V if self.hand.is_empty() => {
+                let variant = self.rules.variant.next();
+                self.leave_game(ctx, NextGame::Variant(variant));
+            }
+            KeyCode::T => {
+                rules.takeback = rules.takeback.next();
+                let notice = format!("Takebacks {} from the next deal", rules.takeback);
+                self.save_settings();
+                self.status.notify(&notice);
+            }
+            KeyCode::W => {
+                rules.winnable_only = !rules.winnable_only;
+                self.save_settings();
             }
+            KeyCode::D => {
+                rules.difficulty = Difficulty::next_request(rules.difficulty);
+                self.save_settings();
+            }
+            KeyCode::C if self.hand.is_empty() => self.leave_game(ctx, NextGame::Daily),
+            KeyCode::S if self.hand.is_empty() => self.save_game(),
+            KeyCode::R if self.hand.is_empty() => self.leave_game(ctx, NextGame::Restart),
+            KeyCode::U if self.hand.is_empty() && !self.is_victory() => self.undo(ctx),
+            _ => {}
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if let Some(screen) = &mut self.screen {
+            let pos = table_position(ctx, x, y);
+            screen.menu_mut().hover(ctx, pos);
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        self.screen_text(character);
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if self.is_victory() || self.search.is_some() || self.screen.is_some() {
+            return;
+        }
+        if button == MouseButton::Left && self.settings.input.mode == InputMode::Drag {
+            let pos = table_position(ctx, x, y);
+            self.drop_hand(ctx, pos);
         }
     }
 
     fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if let Some(screen) = &mut self.screen {
+            if button == MouseButton::Left {
+                let pos = table_position(ctx, x, y);
+                if let Some(event) = screen.menu_mut().click(ctx, pos) {
+                    self.screen_event(ctx, event);
+                }
+            }
+            return;
+        }
+        if self.search.is_some() {
+            return;
+        }
         if self.is_victory() {
             if self.finale.is_playing() {
                 self.reset(ctx);
@@ -152,29 +954,43 @@ impl EventHandler<ggez::GameError> for Game {
         }
         match button {
             MouseButton::Right => {
-                let pos = vector![x as i32, y as i32];
-                for c in self.cascades.iter_mut() {
+                let pos = table_position(ctx, x, y);
+                for (i, c) in self.cascades.iter_mut().enumerate() {
                     if c.inside(pos) && c.cards_to_take(pos) == 1 {
                         if let Some(card_to_stack) = c.bottom_card() {
-                            for f in self.foundations.iter_mut() {
+                            for (j, f) in self.foundations.iter_mut().enumerate() {
                                 if f.can_stack(card_to_stack) {
                                     f.put(c.take(1).pop().unwrap());
                                     self.hand_card_source = None;
-                                    self.play_send(ctx);
+                                    self.moves += 1;
+                                    self.history.push(Move {
+                                        from: Pile::Cascade(i),
+                                        to: Pile::Foundation(j),
+                                        count: 1,
+                                    });
+                                    self.play(ctx, Effect::Send);
+                                    self.auto_play(ctx);
                                     return;
                                 }
                             }
                         }
                     }
                 }
-                for c in self.open_cells.iter_mut() {
+                for (i, c) in self.open_cells.iter_mut().enumerate() {
                     if c.inside(pos) {
                         if let Some(card_to_stack) = c.card() {
-                            for f in self.foundations.iter_mut() {
+                            for (j, f) in self.foundations.iter_mut().enumerate() {
                                 if f.can_stack(card_to_stack) {
                                     f.put(c.take().unwrap());
                                     self.hand_card_source = None;
-                                    self.play_send(ctx);
+                                    self.moves += 1;
+                                    self.history.push(Move {
+                                        from: Pile::Cell(i),
+                                        to: Pile::Foundation(j),
+                                        count: 1,
+                                    });
+                                    self.play(ctx, Effect::Send);
+                                    self.auto_play(ctx);
                                     return;
                                 }
                             }
@@ -183,44 +999,45 @@ impl EventHandler<ggez::GameError> for Game {
                 }
             }
             MouseButton::Left => {
-                let pos = vector![x as i32, y as i32];
+                let pos = table_position(ctx, x, y);
 
                 if self.button.inside(pos) {
-                    self.reset(ctx);
+                    self.leave_game(ctx, NextGame::New);
                     return;
                 }
 
-                if self.hand.is_empty() {
-                    let movable_cards = 2usize.pow(self.empty_cascade_count() as u32)
-                        * (self.empty_cell_count() + 1);
-                    for (i, c) in self.cascades.iter_mut().enumerate() {
-                        if c.inside(pos) {
-                            let cards_to_take = c.cards_to_take(pos);
-                            if cards_to_take <= movable_cards
-                                && c.has_alternating_color_cards(cards_to_take)
-                            {
-                                self.hand.put(ctx, c.take(cards_to_take));
-                                self.hand_card_source = Some(CardSource::Cascade(i));
-                                return;
-                            }
-                        }
+                if !self.hand.is_empty() {
+                    // Only in click mode, where cards stay in hand between clicks.
+                    self.drop_hand(ctx, pos);
+                    return;
+                }
+                if let Some(i) = self.cascades.iter().position(|c| c.inside(pos)) {
+                    let (cards_to_take, movable) = self.run_at(i, pos);
+                    if movable {
+                        let cards = self.cascades[i].take(cards_to_take);
+                        self.hand.put(ctx, cards);
+                        self.hand_card_source = Some(Pile::Cascade(i));
+                        return;
                     }
-                    for (i, c) in self.open_cells.iter_mut().enumerate() {
-                        if c.inside(pos) {
-                            if let Some(card) = c.take() {
-                                self.hand.put(ctx, vec![card]);
-                                self.hand_card_source = Some(CardSource::Cell(i));
-                                return;
-                            }
+                }
+                for (i, c) in self.open_cells.iter_mut().enumerate() {
+                    if c.inside(pos) {
+                        if let Some(card) = c.take() {
+                            self.hand.put(ctx, vec![card]);
+                            self.hand_card_source = Some(Pile::Cell(i));
+                            return;
                         }
                     }
-                    for (i, f) in self.foundations.iter_mut().enumerate() {
-                        if f.inside(pos) {
-                            if let Some(card) = f.take() {
-                                self.hand.put(ctx, vec![card]);
-                                self.hand_card_source = Some(CardSource::Foundation(i));
-                                return;
-                            }
+                }
+                if self.takeback == Takeback::Forbidden {
+                    return;
+                }
+                for (i, f) in self.foundations.iter_mut().enumerate() {
+                    if f.inside(pos) {
+                        if let Some(card) = f.take() {
+                            self.hand.put(ctx, vec![card]);
+                            self.hand_card_source = Some(Pile::Foundation(i));
+                            return;
                         }
                     }
                 }
@@ -231,8 +1048,53 @@ impl EventHandler<ggez::GameError> for Game {
 
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
         self.hand.update(ctx)?;
+        self.status.update(ctx)?;
+        if self.hand.has_returned() {
+            self.return_hand(ctx);
+        }
+        self.highlight_targets();
+        self.hover_cascades(ctx);
+
+        if let Some(found) = self.search.as_ref().and_then(|s| s.poll()) {
+            self.search = None;
+            if !found.fallback {
+                self.solvable_deals.insert(self.rules.variant.id, found.id);
+            }
+            self.deal(ctx, found.id);
+        }
+
+        if self.search.is_some() {
+            self.status.set_text(&match self.settings.rules.difficulty {
+                Some(difficulty) => format!("Looking for {} deal...", article(difficulty)),
+                None => "Looking for a winnable deal...".to_string(),
+            });
+        } else {
+            self.status.set_text(&format!(
+                "{}Moves: {}    Time: {}    Score: {}\n\
+                 Takebacks: {}    Winnable only: {}    Difficulty: {}",
+                self.deal_label(),
+                self.moves,
+                stats::format_time(self.elapsed()),
+                self.score(),
+                self.takeback,
+                if self.settings.rules.winnable_only {
+                    "on"
+                } else {
+                    "off"
+                },
+                self.settings
+                    .rules
+                    .difficulty
+                    .map_or("any".to_string(), |d| d.to_string())
+            ));
+        }
 
         if self.is_victory() {
+            if self.finished.is_none() {
+                let previous = self.finish();
+                self.celebrate(ctx);
+                self.open_summary(previous);
+            }
             self.finale.update(ctx)?;
         }
         std::thread::yield_now();
@@ -240,7 +1102,7 @@ impl EventHandler<ggez::GameError> for Game {
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        graphics::clear(ctx, Color::from_rgb(19, 147, 40));
+        self.table.draw(ctx)?;
         self.tileset.lock().unwrap().clear_queue();
         for f in self.foundations.iter_mut() {
             f.draw(ctx)?;
@@ -253,12 +1115,20 @@ impl EventHandler<ggez::GameError> for Game {
         }
         self.hand.draw(ctx)?;
         self.button.draw(ctx)?;
+        self.status.draw(ctx)?;
 
         if self.is_victory() {
             self.finale.draw(ctx)?;
         }
 
         self.tileset.lock().unwrap().draw(ctx)?;
+        // The summary tells as much as the panel, which would show under it.
+        if self.is_victory() && !matches!(self.screen, Some(Screen::Summary(_))) {
+            self.finale.draw_panel(ctx)?;
+        }
+        if let Some(screen) = &mut self.screen {
+            screen.menu_mut().draw(ctx)?;
+        }
         graphics::present(ctx)?;
         std::thread::yield_now();
         Ok(())
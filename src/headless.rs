@@ -0,0 +1,305 @@
+use crate::board::Board;
+use crate::cli::{self, Options};
+use crate::deal::{Deal, NUMBERED_DEALS};
+use crate::difficulty::{self, Difficulty, RatingCache};
+use crate::notation;
+use crate::solver::{self, Limits, Outcome};
+use crate::tui::{self, Tui};
+use crate::variant::Variant;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Exit status for a deal without solution, an invalid layout, or moves
+/// which do not win.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status for a command line which could not be understood.
+pub const EXIT_USAGE: i32 = 2;
+/// Exit status when the solver reached its limits without a conclusion.
+pub const EXIT_GAVE_UP: i32 = 3;
+/// Deals rated between two saves of the rating cache.
+const RATINGS_PER_SAVE: usize = 100;
+
+/// The position to start from, or the exit status after reporting why it
+/// could not be read.
+fn board(options: Options) -> Result<Board, i32> {
+    match cli::start(options, Variant::FREECELL) {
+        Ok(start) => Ok(Board::from(&start.deal)),
+        Err(e) => {
+            eprintln!("freecell: {}", e);
+            Err(EXIT_FAILURE)
+        }
+    }
+}
+
+/// Print a solution of the deal in standard notation, one move per line.
+pub fn solve(options: Options, nodes: usize) -> i32 {
+    let mut board = match board(options) {
+        Ok(board) => board,
+        Err(status) => return status,
+    };
+    let limits = Limits {
+        nodes,
+        deadline: None,
+    };
+    match solver::solve(&board, &limits) {
+        Outcome::Solved(solution) => {
+            for m in solution.moves.iter() {
+                println!("{}", notation::format_move(&board, m));
+                board.apply(m);
+            }
+            eprintln!(
+                "Solved in {} moves, {} positions explored",
+                solution.moves.len(),
+                solution.nodes
+            );
+            0
+        }
+        Outcome::Unsolvable => {
+            eprintln!("No solution");
+            EXIT_FAILURE
+        }
+        Outcome::GaveUp => {
+            eprintln!("No solution found within {} positions", nodes);
+            EXIT_GAVE_UP
+        }
+    }
+}
+
+/// Check that the layout file of `options` describes a complete position.
+pub fn validate(options: Options) -> i32 {
+    match board(options) {
+        Ok(_) => {
+            println!("Valid");
+            0
+        }
+        Err(status) => status,
+    }
+}
+
+/// Play the moves read from `moves`, stopping at the first illegal one, and
+/// succeed if they win the game.
+pub fn replay(options: Options, moves: &Path) -> i32 {
+    let mut board = match board(options) {
+        Ok(board) => board,
+        Err(status) => return status,
+    };
+    let text = if moves == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        fs::read_to_string(moves)
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("freecell: Could not read {}: {}", moves.display(), e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    let mut played = 0;
+    for word in text.split_whitespace() {
+        let m = match notation::parse_move(&board, word) {
+            Ok(m) if board.is_legal(&m) => m,
+            Ok(_) => {
+                eprintln!("Move {} ({}) is illegal", played + 1, word);
+                return EXIT_FAILURE;
+            }
+            Err(e) => {
+                eprintln!("Move {}: {}", played + 1, e);
+                return EXIT_FAILURE;
+            }
+        };
+        board.apply(&m);
+        played += 1;
+    }
+    if board.is_won() {
+        println!("Won in {} moves", played);
+        0
+    } else {
+        println!("Not won after {} moves", played);
+        EXIT_FAILURE
+    }
+}
+
+/// Rate every numbered deal of `variant` missing from the rating cache,
+/// saving the cache as it goes so that rating can be stopped and resumed.
+pub fn rate(variant: Variant) -> i32 {
+    let mut cache = RatingCache::load();
+    let save = |cache: &RatingCache| match cache.save() {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("freecell: Could not save the ratings: {}", e);
+            false
+        }
+    };
+    let mut rated = 0;
+    for number in NUMBERED_DEALS {
+        if cache.get(variant.id, number).is_some() {
+            continue;
+        }
+        let board = Board::from(&Deal::numbered(variant, number));
+        cache.insert(variant.id, number, difficulty::rate(&board, None));
+        rated += 1;
+        if rated % RATINGS_PER_SAVE == 0 {
+            if !save(&cache) {
+                return EXIT_FAILURE;
+            }
+            eprintln!("Rated deals up to {}", number);
+        }
+    }
+    if !save(&cache) {
+        return EXIT_FAILURE;
+    }
+    for difficulty in Difficulty::ALL {
+        let deals = cache.deals(variant.id, difficulty).len();
+        println!("{}: {} deals", difficulty, deals);
+    }
+    0
+}
+
+/// Play the deal of `options` in the terminal.
+pub fn play_in_terminal(options: Options, plain: bool) -> i32 {
+    let start = match cli::start(options, Variant::FREECELL) {
+        Ok(start) => start,
+        Err(e) => {
+            eprintln!("freecell: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let game = Tui::new(&start.deal, start.deal_id);
+    let result = if plain {
+        tui::run_plain(game)
+    } else {
+        tui::run(game)
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("freecell: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Opening;
+    use crate::deal::DealId;
+    use crate::save::SavedGame;
+    use std::path::PathBuf;
+
+    /// A file of the temporary directory holding `contents`, named after the
+    /// test writing it.
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("freecell-{}", name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn load(path: PathBuf) -> Options {
+        Options {
+            variant: None,
+            opening: Opening::Load(path),
+            window_size: None,
+            mute: false,
+        }
+    }
+
+    #[test]
+    fn saved_games_only_replay_legal_moves() {
+        let deal = Deal::from_id(Variant::FREECELL, DealId::Number(1));
+        let saved = SavedGame {
+            variant: "freecell".to_string(),
+            deal: Some(DealId::Number(1)),
+            base_rank: deal.base_rank,
+            cascades: deal.cascades.clone(),
+            cells: deal.cells.clone(),
+            foundations: deal.foundations.clone(),
+            moves: 0,
+            penalty: 0,
+            seconds: 0,
+        };
+        let save = std::env::temp_dir().join("freecell-replay-save.json");
+        saved.save(&save).unwrap();
+        // A cascade whose last card is no ace cannot go to the foundations.
+        let cascade = deal
+            .cascades
+            .iter()
+            .position(|c| c.last().unwrap().value != 0)
+            .unwrap();
+        let illegal = format!("{}h", notation::CASCADES.chars().nth(cascade).unwrap());
+        let moves = temp_file("replay-illegal.txt", illegal.as_bytes());
+        assert_eq!(replay(load(save.clone()), &moves), EXIT_FAILURE);
+        let moves = temp_file("replay-garbled.txt", b"1z");
+        assert_eq!(replay(load(save.clone()), &moves), EXIT_FAILURE);
+        // Legal moves which do not win fail as well.
+        let moves = temp_file("replay-none.txt", b"");
+        assert_eq!(replay(load(save), &moves), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn unreadable_saves_fail() {
+        let save = temp_file("replay-broken.json", b"{}");
+        let moves = temp_file("replay-unused.txt", b"");
+        assert_eq!(replay(load(save), &moves), EXIT_FAILURE);
+    }
+
+    fn board_file(name: &str, layout: &str) -> Options {
+        Options {
+            variant: None,
+            opening: Opening::Board(temp_file(name, layout.as_bytes())),
+            window_size: None,
+            mute: false,
+        }
+    }
+
+    /// Deal 1 of the Microsoft game.
+    const DEAL_1: &str = "\
+        : JD KD 2S 4C 3S 6D 6S
+        : 2D KC KS 5C TD 8S 9C
+        : 9H 9S 9D TS 4S 8D 2H
+        : JC 5S QD QH TH QS 6H
+        : 5D AD JS 4H 8H 6C
+        : 7H QC AS AC 2C 3D
+        : 7C KH AH 4D JH 8C
+        : 5H 3H 3C 7S 7D TC
+    ";
+
+    #[test]
+    fn exit_statuses_are_as_documented() {
+        assert!(cli::USAGE.contains(&format!("{} when a deal has no solution", EXIT_FAILURE)));
+        assert!(cli::USAGE.contains(&format!("{} on usage errors", EXIT_USAGE)));
+        assert!(cli::USAGE.contains(&format!("{} when the solver gives up", EXIT_GAVE_UP)));
+    }
+
+    #[test]
+    fn validate_checks_the_layout() {
+        assert_eq!(validate(board_file("validate-good.txt", DEAL_1)), 0);
+        let missing = DEAL_1.replacen(" TC", "", 1);
+        assert_eq!(
+            validate(board_file("validate-bad.txt", &missing)),
+            EXIT_FAILURE
+        );
+        let unreadable = std::env::temp_dir().join("freecell-validate-missing.txt");
+        let _ = fs::remove_file(&unreadable);
+        let options = Options {
+            variant: None,
+            opening: Opening::Board(unreadable),
+            window_size: None,
+            mute: false,
+        };
+        assert_eq!(validate(options), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn solve_gives_up_at_its_limit() {
+        assert_eq!(
+            solve(board_file("solve-limited.txt", DEAL_1), 1),
+            EXIT_GAVE_UP
+        );
+        assert_eq!(solve(board_file("solve.txt", DEAL_1), 200_000), 0);
+    }
+}
@@ -1,19 +1,64 @@
 use game::Game;
+use ggez::conf::ModuleConf;
 use ggez::event;
 use ggez::graphics;
-use ggez::ContextBuilder;
-use nalgebra::Vector2;
+use ggez::{ContextBuilder, GameError};
+use settings::Settings;
 
+mod board;
 mod card;
+mod cli;
 mod components;
+mod daily;
+mod deal;
+mod difficulty;
 mod game;
+mod headless;
+mod notation;
+mod save;
+mod search;
+mod settings;
+mod solver;
+mod sound;
+mod stats;
+mod storage;
+mod theme;
 mod tileset;
-
-trait Collision {
-    fn inside(&self, pos: Vector2<i32>) -> bool;
-}
+mod tui;
+mod variant;
 
 fn main() {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Play(options)) => options,
+        Ok(cli::Command::Solve { options, nodes }) => {
+            std::process::exit(headless::solve(options, nodes))
+        }
+        Ok(cli::Command::Validate(options)) => std::process::exit(headless::validate(options)),
+        Ok(cli::Command::Replay { options, moves }) => {
+            std::process::exit(headless::replay(options, &moves))
+        }
+        Ok(cli::Command::Tui { options, plain }) => {
+            std::process::exit(headless::play_in_terminal(options, plain))
+        }
+        Ok(cli::Command::Rate(variant)) => std::process::exit(headless::rate(variant)),
+        Ok(cli::Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("freecell: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(headless::EXIT_USAGE);
+        }
+    };
+    let settings = Settings::load().unwrap_or_else(|e| {
+        eprintln!("freecell: {}, using the default settings", e);
+        Settings::default()
+    });
+    let mut start = cli::start(options, settings.variant()).unwrap_or_else(|e| {
+        eprintln!("freecell: {}", e);
+        std::process::exit(headless::EXIT_FAILURE);
+    });
+
     let resource_dir = if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
         let mut path = std::path::PathBuf::from(manifest_dir);
         path.push("resources");
@@ -22,11 +67,22 @@ fn main() {
         std::path::PathBuf::from("./resources")
     };
 
-    let (mut ctx, event_loop) = ContextBuilder::new("freecell", "Freecell")
-        .add_resource_path(resource_dir)
-        .build()
-        .unwrap();
+    let build = |audio| {
+        ContextBuilder::new("freecell", "Freecell")
+            .add_resource_path(&resource_dir)
+            .modules(ModuleConf::default().audio(audio))
+            .build()
+    };
+    let (mut ctx, event_loop) = match build(true) {
+        Err(GameError::AudioError(_)) => {
+            eprintln!("freecell: no audio device could be opened, playing without sound");
+            start.audio = false;
+            build(false)
+        }
+        built => built,
+    }
+    .unwrap();
     graphics::set_window_title(&ctx, "Freecell");
-    let game = Game::new(&mut ctx);
+    let game = Game::new(&mut ctx, start, settings);
     event::run(ctx, event_loop, game);
 }
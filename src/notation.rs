@@ -0,0 +1,449 @@
+use crate::board::{Board, Move, Pile};
+use crate::card::Card;
+use crate::deal::Deal;
+use crate::variant::{BaseRank, Variant};
+use std::collections::HashSet;
+use std::fmt;
+
+const RANKS: &str = "A23456789TJQK";
+/// Suit letters in the order of the tilesheet rows.
+const SUITS: &str = "SHCD";
+/// Cascades in move notation, the tenth being written `0`.
+pub const CASCADES: &str = "1234567890";
+/// Free cells in move notation, skipping `h` which stands for the foundations.
+pub const CELLS: &str = "abcdefgijk";
+/// The foundations in move notation.
+pub const FOUNDATIONS: char = 'h';
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            RANKS.as_bytes()[self.value as usize] as char,
+            SUITS.as_bytes()[self.suit as usize] as char
+        )
+    }
+}
+
+/// Errors from reading cards and layouts written as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    /// A card was not a rank (A, 2-9, T or 10, J, Q, K) followed by a suit
+    /// (C, D, H, S).
+    BadCard(String),
+    /// A foundation was not a suit and rank such as `H-5`.
+    BadFoundation(String),
+    BadBaseRank(String),
+    /// A card is missing from the layout.
+    MissingCard(Card),
+    /// A card appears more often than there are decks.
+    ExtraCard(Card),
+    /// A move was not written as a source and a destination such as `3a`,
+    /// or its piles do not exist or hold no card to move.
+    BadMove(String),
+    /// The layout does not have as many piles as the variant.
+    PileCount {
+        pile: &'static str,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::BadCard(s) => write!(f, "\"{}\" is not a card", s),
+            NotationError::BadFoundation(s) => write!(f, "\"{}\" is not a foundation", s),
+            NotationError::BadBaseRank(s) => write!(f, "\"{}\" is not a rank", s),
+            NotationError::MissingCard(card) => write!(f, "{} is missing", card),
+            NotationError::ExtraCard(card) => write!(f, "{} appears too many times", card),
+            NotationError::BadMove(s) => write!(f, "\"{}\" is not a possible move", s),
+            NotationError::PileCount {
+                pile,
+                expected,
+                found,
+            } => write!(f, "Expected {} {}, found {}", expected, pile, found),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+fn parse_rank(s: &str) -> Option<u8> {
+    match s.to_ascii_uppercase().as_str() {
+        "10" => Some(9),
+        r if r.len() == 1 => RANKS.find(r).map(|i| i as u8),
+        _ => None,
+    }
+}
+
+fn parse_suit(s: &str) -> Option<u8> {
+    match s.to_ascii_uppercase().as_str() {
+        r if r.len() == 1 => SUITS.find(r).map(|i| i as u8),
+        _ => None,
+    }
+}
+
+/// Read a card such as `QH` or `10s`, from the first deck.
+pub fn parse_card(s: &str) -> Result<Card, NotationError> {
+    let bad = || NotationError::BadCard(s.to_string());
+    let split = s
+        .len()
+        .checked_sub(1)
+        .filter(|&i| s.is_char_boundary(i))
+        .ok_or_else(bad)?;
+    let (rank, suit) = s.split_at(split);
+    Ok(Card {
+        value: parse_rank(rank).ok_or_else(bad)?,
+        suit: parse_suit(suit).ok_or_else(bad)?,
+        deck: 0,
+    })
+}
+
+/// Gives identical cards increasing deck numbers as they are met.
+#[derive(Default)]
+struct DeckCounter {
+    seen: Vec<Card>,
+}
+
+impl DeckCounter {
+    fn assign(&mut self, mut card: Card) -> Card {
+        card.deck = self.seen.iter().filter(|c| **c == card).count() as u8;
+        self.seen.push(card.clone());
+        card
+    }
+}
+
+/// Read a layout for `variant` in the format of Freecell Solver boards:
+///
+/// ```text
+/// Foundations: H-2 C-A S-0 D-0
+/// Freecells: 3D - JS -
+/// : 4C 2C 9C 8C QS 4S 2H
+/// : 5H QH 3C AC 3H 4H QD
+/// ...
+/// ```
+///
+/// There is one line per cascade, listed from the card furthest back, the
+/// leading colons being optional. The `Foundations:` and `Freecells:` lines
+/// may be left out when empty, and the foundations of variants with a base
+/// rank set by the deal start from the rank given on a `Base:` line.
+pub fn parse_layout(variant: Variant, text: &str) -> Result<Deal, NotationError> {
+    let mut decks = DeckCounter::default();
+    let mut cascades = Vec::new();
+    let mut cells = Vec::new();
+    let mut foundation_tops = Vec::new();
+    let mut base_rank = 0;
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(rest) = line.strip_prefix("Foundations:") {
+            for foundation in rest.split_whitespace() {
+                let bad = || NotationError::BadFoundation(foundation.to_string());
+                let (suit, rank) = foundation.split_once('-').ok_or_else(bad)?;
+                let suit = parse_suit(suit).ok_or_else(bad)?;
+                let rank = match rank {
+                    "0" => None,
+                    rank => Some(parse_rank(rank).ok_or_else(bad)?),
+                };
+                foundation_tops.push((suit, rank));
+            }
+        } else if let Some(rest) = line.strip_prefix("Freecells:") {
+            for cell in rest.split_whitespace() {
+                cells.push(match cell {
+                    "-" => None,
+                    card => Some(decks.assign(parse_card(card)?)),
+                });
+            }
+        } else if let Some(rest) = line.strip_prefix("Base:") {
+            let rank = rest.trim();
+            base_rank =
+                parse_rank(rank).ok_or_else(|| NotationError::BadBaseRank(rank.to_string()))?;
+        } else {
+            let cards = line.trim_start_matches(':').split_whitespace();
+            cascades.push(
+                cards
+                    .map(|card| parse_card(card).map(|c| decks.assign(c)))
+                    .collect::<Result<Vec<Card>, _>>()?,
+            );
+        }
+    }
+
+    if variant.base_rank == BaseRank::Ace {
+        base_rank = 0;
+    }
+    let mut foundations = vec![Vec::new(); variant.foundations()];
+    for (foundation, (suit, top)) in foundations.iter_mut().zip(foundation_tops.iter()) {
+        if let Some(top) = top {
+            let mut value = base_rank;
+            loop {
+                foundation.push(decks.assign(Card {
+                    suit: *suit,
+                    value,
+                    deck: 0,
+                }));
+                if value == *top {
+                    break;
+                }
+                value = (value + 1) % 13;
+            }
+        }
+    }
+    cells.resize(variant.cells.max(cells.len()), None);
+
+    let count = |pile, expected, found| {
+        if expected == found {
+            Ok(())
+        } else {
+            Err(NotationError::PileCount {
+                pile,
+                expected,
+                found,
+            })
+        }
+    };
+    count("cascades", variant.cascades, cascades.len())?;
+    count("free cells", variant.cells, cells.len())?;
+    count(
+        "foundations",
+        variant.foundations(),
+        foundation_tops.len().max(variant.foundations()),
+    )?;
+
+    let deal = Deal {
+        variant,
+        cascades,
+        cells,
+        foundations,
+        base_rank,
+    };
+    check_cards(&deal)?;
+    Ok(deal)
+}
+
+/// Check that a layout holds every card of its decks exactly once.
+pub fn check_cards(deal: &Deal) -> Result<(), NotationError> {
+    let mut seen = HashSet::new();
+    let cards = deal
+        .cascades
+        .iter()
+        .flatten()
+        .chain(deal.cells.iter().flatten())
+        .chain(deal.foundations.iter().flatten());
+    for card in cards {
+        if card.deck >= deal.variant.decks || !seen.insert(card) {
+            return Err(NotationError::ExtraCard(card.clone()));
+        }
+    }
+    match Card::deck(deal.variant.decks)
+        .into_iter()
+        .find(|card| !seen.contains(card))
+    {
+        Some(card) => Err(NotationError::MissingCard(card)),
+        None => Ok(()),
+    }
+}
+
+/// How many cards a move between two cascades takes when not written: the
+/// longest run that can go there.
+fn implied_count(board: &Board, from: usize, to: usize) -> usize {
+    let cascade = &board.cascades[from];
+    let limit = board
+        .sequence_length(from)
+        .min(board.movable_cards(Pile::Cascade(to)));
+    (1..=limit)
+        .rev()
+        .find(|&count| {
+            let card = &cascade[cascade.len() - count];
+            match board.cascades[to].last() {
+                Some(bottom) => board.rules.builds_down(card, bottom),
+                None => board.rules.starts_cascade(card),
+            }
+        })
+        .unwrap_or(1)
+}
+
+fn pile_name(pile: Pile) -> char {
+    match pile {
+        Pile::Cascade(i) => CASCADES.as_bytes()[i] as char,
+        Pile::Cell(i) => CELLS.as_bytes()[i] as char,
+        Pile::Foundation(_) => FOUNDATIONS,
+    }
+}
+
+/// Write `m`, to be played on `board`, in standard notation: the source and
+/// destination piles, cascades being numbered from `1`, free cells lettered
+/// from `a` and the foundations written `h`, as in `3a` or `ah`.
+///
+/// A move between cascades takes the longest run which fits, unless a count
+/// follows a slash, as in `38/2`.
+pub fn format_move(board: &Board, m: &Move) -> String {
+    let mut text: String = [pile_name(m.from), pile_name(m.to)].iter().collect();
+    if let (Pile::Cascade(from), Pile::Cascade(to)) = (m.from, m.to) {
+        if m.count != implied_count(board, from, to) {
+            text += &format!("/{}", m.count);
+        }
+    }
+    text
+}
+
+/// Read a move written by `format_move`, to be played on `board`. Whether
+/// the move is legal is left to `Board::is_legal`.
+pub fn parse_move(board: &Board, text: &str) -> Result<Move, NotationError> {
+    let bad = || NotationError::BadMove(text.to_string());
+    let (piles, count) = match text.split_once('/') {
+        Some((piles, count)) => (piles, Some(count.parse().map_err(|_| bad())?)),
+        None => (text, None),
+    };
+    let mut chars = piles.chars();
+    let (from, to) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(from), Some(to), None) => (from.to_ascii_lowercase(), to.to_ascii_lowercase()),
+        _ => return Err(bad()),
+    };
+    let pile = |c: char| {
+        if c == FOUNDATIONS {
+            return Some(Pile::Foundation(0));
+        }
+        match (CASCADES.find(c), CELLS.find(c)) {
+            (Some(i), _) if i < board.cascades.len() => Some(Pile::Cascade(i)),
+            (_, Some(i)) if i < board.cells.len() => Some(Pile::Cell(i)),
+            _ => None,
+        }
+    };
+    let (mut from, mut to) = (pile(from).ok_or_else(bad)?, pile(to).ok_or_else(bad)?);
+
+    let card = match from {
+        Pile::Cascade(i) => board.cascades[i].last(),
+        Pile::Cell(i) => board.cells[i].as_ref(),
+        Pile::Foundation(_) => None,
+    };
+    match (from, to) {
+        (Pile::Foundation(_), Pile::Foundation(_)) => return Err(bad()),
+        // Any foundation whose top card could go there.
+        (Pile::Foundation(_), _) => {
+            let fits = |f: usize| {
+                board.is_legal(&Move {
+                    from: Pile::Foundation(f),
+                    to,
+                    count: 1,
+                })
+            };
+            from = Pile::Foundation((0..board.foundations.len()).find(|&f| fits(f)).unwrap_or(0));
+        }
+        (_, Pile::Foundation(_)) => {
+            let card = card.ok_or_else(bad)?;
+            to = Pile::Foundation(board.foundation_for(card).unwrap_or(0));
+        }
+        _ => {}
+    }
+    let count = match (from, to) {
+        (Pile::Cascade(from), Pile::Cascade(to)) => {
+            if board.cascades[from].is_empty() {
+                return Err(bad());
+            }
+            count.unwrap_or_else(|| implied_count(board, from, to))
+        }
+        _ if count.is_some() => return Err(bad()),
+        _ => 1,
+    };
+    Ok(Move { from, to, count })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::DealId;
+
+    #[test]
+    fn check_cards_takes_each_card_once_per_deck() {
+        let mut deal = Deal::seeded(Variant::DOUBLE_FREECELL, 7);
+        assert_eq!(check_cards(&deal), Ok(()));
+
+        let card = deal.cascades[0][0].clone();
+        let replaced = deal.cascades[1][0].clone();
+        let third = Card {
+            deck: 2,
+            ..card.clone()
+        };
+        deal.cascades[1][0] = third.clone();
+        assert_eq!(check_cards(&deal), Err(NotationError::ExtraCard(third)));
+        deal.cascades[1][0] = card.clone();
+        assert_eq!(check_cards(&deal), Err(NotationError::ExtraCard(card)));
+        deal.cascades[1].remove(0);
+        assert_eq!(
+            check_cards(&deal),
+            Err(NotationError::MissingCard(replaced))
+        );
+    }
+
+    /// Deal 1 of the Microsoft game.
+    const DEAL_1: &str = "\
+        : JD KD 2S 4C 3S 6D 6S
+        : 2D KC KS 5C TD 8S 9C
+        : 9H 9S 9D TS 4S 8D 2H
+        : JC 5S QD QH TH QS 6H
+        : 5D AD JS 4H 8H 6C
+        : 7H QC AS AC 2C 3D
+        : 7C KH AH 4D JH 8C
+        : 5H 3H 3C 7S 7D TC
+    ";
+
+    #[test]
+    fn layouts_read_as_dealt() {
+        let deal = parse_layout(Variant::FREECELL, DEAL_1).unwrap();
+        let dealt = Deal::from_id(Variant::FREECELL, DealId::Number(1));
+        assert_eq!(deal.cascades, dealt.cascades);
+        assert_eq!(deal.cells, vec![None; 4]);
+
+        let text = format!("Foundations: H-2 C-A S-0 D-0\nFreecells: - KD\n{}", DEAL_1)
+            .replacen("KD ", "", 1)
+            .replacen("AC ", "", 1)
+            .replacen("AH ", "", 1)
+            .replacen("2H", "", 1);
+        let deal = parse_layout(Variant::FREECELL, &text).unwrap();
+        assert_eq!(deal.foundations[0].len(), 2);
+        assert_eq!(deal.foundations[1].len(), 1);
+        assert_eq!(
+            deal.cells[1].as_ref().map(Card::to_string),
+            Some("KD".to_string())
+        );
+    }
+
+    #[test]
+    fn incomplete_layouts_are_refused() {
+        assert_eq!(
+            parse_layout(Variant::FREECELL, &DEAL_1.replacen(" TC", "", 1)).err(),
+            Some(NotationError::MissingCard(parse_card("TC").unwrap()))
+        );
+        assert_eq!(
+            parse_layout(Variant::FREECELL, &DEAL_1.replacen("TC", "1X", 1)).err(),
+            Some(NotationError::BadCard("1X".to_string()))
+        );
+        assert_eq!(
+            parse_layout(Variant::DOUBLE_FREECELL, DEAL_1).err(),
+            Some(NotationError::PileCount {
+                pile: "cascades",
+                expected: 10,
+                found: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn moves_read_as_written() {
+        let board = Board::from(&Deal::from_id(Variant::FREECELL, DealId::Number(1)));
+        let round_trip = |board: &Board| {
+            for m in board.moves() {
+                let text = format_move(board, &m);
+                assert_eq!(parse_move(board, &text), Ok(m), "{}", text);
+            }
+        };
+        round_trip(&board);
+        // Further in, with cards in the cells and on the foundations.
+        for m in board.moves() {
+            let mut next = board.clone();
+            next.apply(&m);
+            round_trip(&next);
+        }
+    }
+}
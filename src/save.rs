@@ -0,0 +1,184 @@
+use crate::card::Card;
+use crate::deal::{Deal, DealId};
+use crate::notation::{self, NotationError};
+use crate::variant::Variant;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A game in progress, as written to a save file.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    /// The `Variant::id` of the game.
+    pub variant: String,
+    /// How the game was dealt, if it can be dealt again.
+    pub deal: Option<DealId>,
+    pub base_rank: u8,
+    pub cascades: Vec<Vec<Card>>,
+    pub cells: Vec<Option<Card>>,
+    pub foundations: Vec<Vec<Card>>,
+    pub moves: u32,
+    pub penalty: i32,
+    pub seconds: u64,
+}
+
+/// Possible errors when reading a save file.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnknownVariant(String),
+    /// The saved position is not a complete layout of its variant.
+    Layout(NotationError),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Format(e) => write!(f, "Not a saved game: {}", e),
+            SaveError::UnknownVariant(id) => write!(f, "Unknown variant \"{}\"", id),
+            SaveError::Layout(e) => write!(f, "Not a valid position: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl SavedGame {
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        let bytes = fs::read(path).map_err(SaveError::Io)?;
+        let saved: SavedGame = serde_json::from_slice(&bytes).map_err(SaveError::Format)?;
+        saved.variant()?;
+        Ok(saved)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            path,
+            serde_json::to_vec_pretty(self).map_err(io::Error::from)?,
+        )
+    }
+
+    pub fn variant(&self) -> Result<Variant, SaveError> {
+        Variant::from_id(&self.variant)
+            .ok_or_else(|| SaveError::UnknownVariant(self.variant.clone()))
+    }
+
+    /// The saved position, laid out as a deal to resume from, once checked
+    /// to have the piles of its variant and every card exactly once.
+    pub fn position(&self) -> Result<Deal, SaveError> {
+        let variant = self.variant()?;
+        let count = |pile, expected, found| {
+            if expected == found {
+                Ok(())
+            } else {
+                Err(SaveError::Layout(NotationError::PileCount {
+                    pile,
+                    expected,
+                    found,
+                }))
+            }
+        };
+        count("cascades", variant.cascades, self.cascades.len())?;
+        count("free cells", variant.cells, self.cells.len())?;
+        count("foundations", variant.foundations(), self.foundations.len())?;
+        if self.base_rank >= 13 {
+            return Err(SaveError::Layout(NotationError::BadBaseRank(
+                self.base_rank.to_string(),
+            )));
+        }
+
+        let deal = Deal {
+            variant,
+            cascades: self.cascades.clone(),
+            cells: self.cells.clone(),
+            foundations: self.foundations.clone(),
+            base_rank: self.base_rank,
+        };
+        notation::check_cards(&deal).map_err(SaveError::Layout)?;
+        Ok(deal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved() -> SavedGame {
+        let deal = Deal::from_id(Variant::FREECELL, DealId::Number(1));
+        SavedGame {
+            variant: "freecell".to_string(),
+            deal: Some(DealId::Number(1)),
+            base_rank: deal.base_rank,
+            cascades: deal.cascades,
+            cells: deal.cells,
+            foundations: deal.foundations,
+            moves: 0,
+            penalty: 0,
+            seconds: 0,
+        }
+    }
+
+    #[test]
+    fn position_is_the_saved_layout() {
+        let deal = saved().position().unwrap();
+        assert_eq!(deal.cascades, saved().cascades);
+    }
+
+    #[test]
+    fn position_rejects_an_unknown_variant() {
+        let mut game = saved();
+        game.variant = "spider".to_string();
+        assert!(matches!(
+            game.position(),
+            Err(SaveError::UnknownVariant(id)) if id == "spider"
+        ));
+    }
+
+    #[test]
+    fn position_rejects_missing_piles() {
+        let mut game = saved();
+        game.cells.pop();
+        assert!(matches!(
+            game.position(),
+            Err(SaveError::Layout(NotationError::PileCount {
+                pile: "free cells",
+                expected: 4,
+                found: 3,
+            }))
+        ));
+    }
+
+    #[test]
+    fn position_rejects_a_bad_base_rank() {
+        let mut game = saved();
+        game.base_rank = 13;
+        assert!(matches!(
+            game.position(),
+            Err(SaveError::Layout(NotationError::BadBaseRank(_)))
+        ));
+    }
+
+    #[test]
+    fn position_rejects_lost_and_copied_cards() {
+        let mut game = saved();
+        let card = game.cascades[0].pop().unwrap();
+        assert!(matches!(
+            game.position(),
+            Err(SaveError::Layout(NotationError::MissingCard(c))) if c == card
+        ));
+        let mut game = saved();
+        let card = game.cascades[0][0].clone();
+        game.cells[0] = Some(card.clone());
+        assert!(matches!(
+            game.position(),
+            Err(SaveError::Layout(NotationError::ExtraCard(c))) if c == card
+        ));
+    }
+}
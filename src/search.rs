@@ -0,0 +1,134 @@
+use crate::board::Board;
+use crate::deal::{Deal, DealId, NUMBERED_DEALS};
+use crate::difficulty::{self, Difficulty, RatingCache};
+use crate::solver::{self, Limits, Outcome};
+use crate::variant::Variant;
+use rand::prelude::*;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to look for a deal before settling for a fallback.
+pub const SEARCH_BUDGET: Duration = Duration::from_secs(5);
+/// Positions the solver may explore for each shuffle tried.
+const NODES_PER_SHUFFLE: usize = 100_000;
+
+/// The deal a search settled on.
+#[derive(Clone, Copy, Debug)]
+pub struct Found {
+    pub id: DealId,
+    /// Whether the time ran out and `id` is a fallback rather than a deal
+    /// meeting what was asked.
+    pub fallback: bool,
+}
+
+/// A search for a deal running in a background thread.
+pub struct Search {
+    receiver: Receiver<Found>,
+    /// Dealt should the search thread stop without an answer.
+    fallback: DealId,
+}
+
+impl Search {
+    fn spawn(fallback: DealId, search: impl FnOnce() -> Found + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The game may have stopped waiting for this search.
+            let _ = sender.send(search());
+        });
+        Self { receiver, fallback }
+    }
+
+    /// Shuffle and solve deals of `variant` until one is winnable, settling
+    /// for `fallback` once `budget` runs out.
+    pub fn winnable(variant: Variant, fallback: DealId, budget: Duration) -> Self {
+        Search::spawn(fallback, move || {
+            let deadline = Instant::now() + budget;
+            let mut rng = rand::thread_rng();
+            loop {
+                let id = DealId::Seed(rng.gen());
+                let board = Board::from(&Deal::from_id(variant, id));
+                let limits = Limits {
+                    nodes: NODES_PER_SHUFFLE,
+                    deadline: Some(deadline),
+                };
+                if let Outcome::Solved(_) = solver::solve(&board, &limits) {
+                    return Found {
+                        id,
+                        fallback: false,
+                    };
+                }
+                if Instant::now() >= deadline {
+                    return Found {
+                        id: fallback,
+                        fallback: true,
+                    };
+                }
+            }
+        })
+    }
+
+    /// Pick a numbered deal of `variant` rated `difficulty`, rating new deals
+    /// until one matches if none is cached. Once `budget` runs out, settles
+    /// for the closest rated deal.
+    pub fn rated(variant: Variant, difficulty: Difficulty, budget: Duration) -> Self {
+        Search::spawn(DealId::Seed(variant.solvable_seed), move || {
+            let deadline = Instant::now() + budget;
+            let mut rng = rand::thread_rng();
+            let mut cache = RatingCache::load();
+
+            let cached = cache.deals(variant.id, difficulty);
+            if let Some(number) = cached.choose(&mut rng) {
+                return Found {
+                    id: DealId::Number(*number),
+                    fallback: false,
+                };
+            }
+
+            let found = loop {
+                if Instant::now() >= deadline {
+                    let number = cache
+                        .closest(variant.id, difficulty)
+                        .unwrap_or_else(|| rng.gen_range(NUMBERED_DEALS));
+                    break Found {
+                        id: DealId::Number(number),
+                        fallback: true,
+                    };
+                }
+                let number = rng.gen_range(NUMBERED_DEALS);
+                if cache.get(variant.id, number).is_some() {
+                    continue;
+                }
+                let board = Board::from(&Deal::numbered(variant, number));
+                let rating = difficulty::rate(&board, Some(deadline));
+                if Instant::now() >= deadline {
+                    // The rating may have been cut short.
+                    continue;
+                }
+                cache.insert(variant.id, number, rating);
+                if rating == Some(difficulty) {
+                    break Found {
+                        id: DealId::Number(number),
+                        fallback: false,
+                    };
+                }
+            };
+            // Ratings are only a shortcut, losing them is harmless.
+            let _ = cache.save();
+            found
+        })
+    }
+
+    /// The result of the search, once it is over. A search that stopped
+    /// without an answer, such as by panicking, settles for its fallback.
+    pub fn poll(&self) -> Option<Found> {
+        match self.receiver.try_recv() {
+            Ok(found) => Some(found),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Found {
+                id: self.fallback,
+                fallback: true,
+            }),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use crate::components::{Celebration, Fill};
+use crate::difficulty::Difficulty;
+use crate::sound::{Effect, EffectVolumes, SoundFiles};
+use crate::storage;
+use crate::theme::{self, CardStyle};
+use crate::variant::{Takeback, Variant};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const SETTINGS_FILE: &str = "settings.toml";
+/// Steps the volume goes through on the settings screen.
+const VOLUME_STEP: f32 = 0.1;
+/// Background colours to pick from on the settings screen.
+const BACKGROUNDS: [(&str, [u8; 3]); 5] = [
+    ("green", [19, 147, 40]),
+    ("blue", [25, 70, 140]),
+    ("red", [130, 30, 35]),
+    ("grey", [70, 70, 70]),
+    ("black", [15, 15, 15]),
+];
+
+/// User preferences, kept in `settings.toml` in the user config directory.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: Audio,
+    pub appearance: Appearance,
+    pub rules: RuleSettings,
+    pub input: Input,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Audio {
+    /// Factor applied to the volume of every sound, from 0 to 1.
+    pub volume: f32,
+    pub muted: bool,
+    pub effects: EffectVolumes,
+    pub files: SoundFiles,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self {
+            volume: 1.,
+            muted: false,
+            effects: EffectVolumes::default(),
+            files: SoundFiles::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    /// Colour of the table, as red, green and blue.
+    pub background: [u8; 3],
+    /// An image covering the table in place of the theme's, relative to the
+    /// config directory unless absolute.
+    pub image: Option<PathBuf>,
+    pub fill: Fill,
+    /// A dark table, whatever the colour set.
+    pub dark: bool,
+    /// The card theme, by the name of its directory.
+    pub theme: String,
+    /// Clubs drawn green and diamonds blue.
+    pub four_colour: bool,
+    /// Large indices in the corner of the cards.
+    pub high_contrast: bool,
+    /// How winning a game is celebrated.
+    pub celebration: Celebration,
+}
+
+impl Appearance {
+    pub fn card_style(&self) -> CardStyle {
+        CardStyle {
+            four_colour: self.four_colour,
+            high_contrast: self.high_contrast,
+        }
+    }
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            background: BACKGROUNDS[0].1,
+            image: None,
+            fill: Fill::Tile,
+            dark: false,
+            theme: theme::DEFAULT_THEME.to_string(),
+            four_colour: false,
+            high_contrast: false,
+            celebration: Celebration::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSettings {
+    /// The `Variant::id` dealt at startup.
+    pub variant: String,
+    pub takeback: Takeback,
+    pub winnable_only: bool,
+    pub difficulty: Option<Difficulty>,
+}
+
+impl Default for RuleSettings {
+    fn default() -> Self {
+        Self {
+            variant: Variant::FREECELL.id.to_string(),
+            takeback: Takeback::Allowed,
+            winnable_only: false,
+            difficulty: None,
+        }
+    }
+}
+
+/// How cards are moved with the mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputMode {
+    /// Hold the button down to carry cards.
+    Drag,
+    /// Click once to pick cards up, and again to put them down.
+    Click,
+}
+
+/// Which cards are sent to the foundations without being asked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoPlay {
+    Off,
+    /// Cards no card left on the table could be built on.
+    Safe,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Input {
+    pub mode: InputMode,
+    pub auto_play: AutoPlay,
+    /// Ask before leaving a game in progress for another.
+    pub confirm_abandon: bool,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            mode: InputMode::Drag,
+            auto_play: AutoPlay::Off,
+            confirm_abandon: true,
+        }
+    }
+}
+
+/// Possible errors when reading the settings file.
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Format {
+        error: toml::de::Error,
+        /// Where a copy of the file was kept, before saving the settings
+        /// again replaces it.
+        backup: Option<PathBuf>,
+    },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "{}", e),
+            SettingsError::Format { error, backup } => {
+                write!(f, "Invalid settings: {}", error)?;
+                match backup {
+                    Some(backup) => write!(f, " (a copy is kept in {})", backup.display()),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl Settings {
+    /// Read the settings file, using the defaults if there is none yet. A
+    /// file which cannot be understood is copied to `settings.toml.bak`
+    /// first, as the defaults will be saved over it.
+    pub fn load() -> Result<Self, SettingsError> {
+        let path = match storage::config_file(SETTINGS_FILE) {
+            Some(path) => path,
+            None => return Ok(Settings::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).map_err(|error| {
+                let backup = path.with_extension("toml.bak");
+                SettingsError::Format {
+                    error,
+                    backup: fs::copy(&path, &backup).ok().map(|_| backup),
+                }
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(e) => Err(SettingsError::Io(e)),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = storage::config_file(SETTINGS_FILE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+
+    /// The variant to deal at startup, Freecell if the one set is unknown.
+    pub fn variant(&self) -> Variant {
+        Variant::from_id(&self.rules.variant).unwrap_or(Variant::FREECELL)
+    }
+}
+
+/// A line of the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entry {
+    Volume,
+    Sound,
+    EffectVolume(Effect),
+    Background,
+    DarkMode,
+    Theme,
+    FourColour,
+    HighContrast,
+    Celebration,
+    Variant,
+    Takebacks,
+    WinnableOnly,
+    Difficulty,
+    InputMode,
+    AutoPlay,
+    ConfirmAbandon,
+}
+
+/// The element following `current` in `all`, or preceding it if not
+/// `forward`, wrapping around.
+fn cycle<T: PartialEq + Clone>(all: &[T], current: &T, forward: bool) -> T {
+    let i = all.iter().position(|a| a == current).unwrap_or(0);
+    let next = if forward {
+        (i + 1) % all.len()
+    } else {
+        (i + all.len() - 1) % all.len()
+    };
+    all[next].clone()
+}
+
+/// Turn `volume` up or down by a step, between 0 and 1.
+fn step_volume(volume: &mut f32, forward: bool) {
+    let step = if forward { VOLUME_STEP } else { -VOLUME_STEP };
+    let stepped = (*volume + step).clamp(0., 1.);
+    *volume = (stepped / VOLUME_STEP).round() * VOLUME_STEP;
+}
+
+impl Entry {
+    pub const ALL: [Entry; 21] = [
+        Entry::Volume,
+        Entry::Sound,
+        Entry::EffectVolume(Effect::Deal),
+        Entry::EffectVolume(Effect::Send),
+        Entry::EffectVolume(Effect::Pick),
+        Entry::EffectVolume(Effect::Drop),
+        Entry::EffectVolume(Effect::Finale),
+        Entry::EffectVolume(Effect::Illegal),
+        Entry::Background,
+        Entry::DarkMode,
+        Entry::Theme,
+        Entry::FourColour,
+        Entry::HighContrast,
+        Entry::Celebration,
+        Entry::Variant,
+        Entry::Takebacks,
+        Entry::WinnableOnly,
+        Entry::Difficulty,
+        Entry::InputMode,
+        Entry::AutoPlay,
+        Entry::ConfirmAbandon,
+    ];
+
+    pub fn label(&self) -> String {
+        let name = match self {
+            Entry::Volume => "Volume",
+            Entry::Sound => "Sound",
+            Entry::EffectVolume(effect) => return format!("  {} volume", effect.name()),
+            Entry::Background => "Background",
+            Entry::DarkMode => "Dark mode",
+            Entry::Theme => "Cards",
+            Entry::FourColour => "Four-colour deck",
+            Entry::HighContrast => "High contrast",
+            Entry::Celebration => "Victory celebration",
+            Entry::Variant => "Variant",
+            Entry::Takebacks => "Takebacks",
+            Entry::WinnableOnly => "Winnable only",
+            Entry::Difficulty => "Difficulty",
+            Entry::InputMode => "Moving cards",
+            Entry::AutoPlay => "Auto-play",
+            Entry::ConfirmAbandon => "Confirm abandoning a game",
+        };
+        name.to_string()
+    }
+
+    pub fn value(&self, settings: &Settings) -> String {
+        let on_off = |on| if on { "on" } else { "off" }.to_string();
+        let percent = |volume: f32| format!("{:.0}%", volume * 100.);
+        match self {
+            Entry::Volume => percent(settings.audio.volume),
+            Entry::Sound => on_off(!settings.audio.muted),
+            Entry::EffectVolume(effect) => percent(settings.audio.effects.get(*effect)),
+            Entry::Background => BACKGROUNDS
+                .iter()
+                .find(|(_, colour)| *colour == settings.appearance.background)
+                .map_or("custom", |(name, _)| name)
+                .to_string(),
+            Entry::DarkMode => on_off(settings.appearance.dark),
+            Entry::Theme => settings.appearance.theme.clone(),
+            Entry::FourColour => on_off(settings.appearance.four_colour),
+            Entry::HighContrast => on_off(settings.appearance.high_contrast),
+            Entry::Celebration => settings.appearance.celebration.to_string(),
+            Entry::Variant => settings.variant().name.to_string(),
+            Entry::Takebacks => settings.rules.takeback.to_string(),
+            Entry::WinnableOnly => on_off(settings.rules.winnable_only),
+            Entry::Difficulty => settings
+                .rules
+                .difficulty
+                .map_or("any".to_string(), |d| d.to_string()),
+            Entry::InputMode => match settings.input.mode {
+                InputMode::Drag => "drag and drop",
+                InputMode::Click => "click to pick up and put down",
+            }
+            .to_string(),
+            Entry::AutoPlay => match settings.input.auto_play {
+                AutoPlay::Off => "off",
+                AutoPlay::Safe => "safe cards",
+            }
+            .to_string(),
+            Entry::ConfirmAbandon => on_off(settings.input.confirm_abandon),
+        }
+    }
+
+    /// Change the setting to its next value, or previous if not `forward`.
+    pub fn change(&self, settings: &mut Settings, forward: bool) {
+        match self {
+            Entry::Volume => step_volume(&mut settings.audio.volume, forward),
+            Entry::Sound => settings.audio.muted = !settings.audio.muted,
+            Entry::EffectVolume(effect) => {
+                step_volume(settings.audio.effects.get_mut(*effect), forward)
+            }
+            Entry::Background => {
+                let colours: Vec<[u8; 3]> = BACKGROUNDS.iter().map(|(_, c)| *c).collect();
+                settings.appearance.background =
+                    cycle(&colours, &settings.appearance.background, forward);
+            }
+            Entry::DarkMode => settings.appearance.dark = !settings.appearance.dark,
+            Entry::Theme => {
+                settings.appearance.theme =
+                    cycle(&theme::names(), &settings.appearance.theme, forward)
+            }
+            Entry::FourColour => settings.appearance.four_colour = !settings.appearance.four_colour,
+            Entry::HighContrast => {
+                settings.appearance.high_contrast = !settings.appearance.high_contrast
+            }
+            Entry::Celebration => {
+                settings.appearance.celebration =
+                    cycle(&Celebration::ALL, &settings.appearance.celebration, forward)
+            }
+            Entry::Variant => {
+                settings.rules.variant = cycle(Variant::ALL, &settings.variant(), forward)
+                    .id
+                    .to_string()
+            }
+            Entry::Takebacks => {
+                let all = [Takeback::Allowed, Takeback::Forbidden, Takeback::Penalized];
+                settings.rules.takeback = cycle(&all, &settings.rules.takeback, forward);
+            }
+            Entry::WinnableOnly => settings.rules.winnable_only = !settings.rules.winnable_only,
+            Entry::Difficulty => {
+                let mut all = vec![None];
+                all.extend(Difficulty::ALL.iter().copied().map(Some));
+                settings.rules.difficulty = cycle(&all, &settings.rules.difficulty, forward);
+            }
+            Entry::InputMode => {
+                let all = [InputMode::Drag, InputMode::Click];
+                settings.input.mode = cycle(&all, &settings.input.mode, forward);
+            }
+            Entry::AutoPlay => {
+                let all = [AutoPlay::Off, AutoPlay::Safe];
+                settings.input.auto_play = cycle(&all, &settings.input.auto_play, forward);
+            }
+            Entry::ConfirmAbandon => {
+                settings.input.confirm_abandon = !settings.input.confirm_abandon
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_survive_toml() {
+        let mut settings = Settings::default();
+        settings.audio.volume = 0.5;
+        settings.audio.muted = true;
+        settings.audio.effects.illegal = 0.3;
+        settings.audio.files.finale = Some(PathBuf::from("fanfare.ogg"));
+        settings.appearance.background = BACKGROUNDS[2].1;
+        settings.appearance.image = Some(PathBuf::from("/tmp/felt.png"));
+        settings.appearance.fill = Fill::Stretch;
+        settings.appearance.dark = true;
+        settings.appearance.four_colour = true;
+        settings.appearance.celebration = Celebration::Bounce;
+        settings.rules.variant = Variant::PENGUIN.id.to_string();
+        settings.rules.takeback = Takeback::Penalized;
+        settings.rules.winnable_only = true;
+        settings.rules.difficulty = Some(Difficulty::Expert);
+        settings.input.mode = InputMode::Click;
+        settings.input.auto_play = AutoPlay::Safe;
+        settings.input.confirm_abandon = false;
+
+        let text = toml::to_string(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&text).unwrap(), settings);
+        let text = toml::to_string(&Settings::default()).unwrap();
+        assert_eq!(
+            toml::from_str::<Settings>(&text).unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn missing_settings_are_the_defaults() {
+        let settings: Settings = toml::from_str("[rules]\nwinnable_only = true\n").unwrap();
+        assert!(settings.rules.winnable_only);
+        assert_eq!(settings.audio, Audio::default());
+        assert_eq!(settings.rules.takeback, Takeback::Allowed);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::board::{Board, Move, Pile};
+use crate::card::{is_red_suit, Card};
+use crate::variant::Building;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+/// How much searching the solver may do before giving up.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// Maximum number of positions to explore.
+    pub nodes: usize,
+    pub deadline: Option<Instant>,
+}
+
+/// A winning sequence of moves, along with what it took to find it.
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub moves: Vec<Move>,
+    /// Number of positions explored before finding the solution.
+    pub nodes: usize,
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Solved(Solution),
+    /// Every reachable position was explored without winning.
+    Unsolvable,
+    /// The limits were reached before a conclusion.
+    GaveUp,
+}
+
+/// A position waiting to be explored, ordered so that the most promising
+/// comes out of the heap first.
+struct Candidate {
+    priority: i32,
+    node: usize,
+    board: Board,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+/// Search for a way to win from `board` with a best-first search.
+///
+/// Cards which can no longer be useful on the table are sent to the
+/// foundations as soon as possible, and those moves are part of the solution.
+pub fn solve(board: &Board, limits: &Limits) -> Outcome {
+    let mut start = board.clone();
+    let start_moves = auto_play(&mut start);
+
+    // Each explored node records its parent and the moves leading from it.
+    let mut nodes: Vec<(Option<usize>, Vec<Move>)> = vec![(None, start_moves)];
+    let mut seen = HashSet::new();
+    let mut heap = BinaryHeap::new();
+    seen.insert(fingerprint(&start));
+    heap.push(Candidate {
+        priority: heuristic(&start),
+        node: 0,
+        board: start,
+    });
+
+    while let Some(Candidate { node, board, .. }) = heap.pop() {
+        if board.is_won() {
+            return Outcome::Solved(solution(&nodes, node));
+        }
+        if nodes.len() >= limits.nodes {
+            return Outcome::GaveUp;
+        }
+        if let Some(deadline) = limits.deadline {
+            if nodes.len().is_multiple_of(256) && Instant::now() >= deadline {
+                return Outcome::GaveUp;
+            }
+        }
+
+        let depth = path_length(&nodes, node) as i32;
+        for m in board.moves() {
+            let mut next = board.clone();
+            next.apply(&m);
+            let mut moves = vec![m];
+            moves.extend(auto_play(&mut next));
+            if !seen.insert(fingerprint(&next)) {
+                continue;
+            }
+            nodes.push((Some(node), moves));
+            heap.push(Candidate {
+                priority: 2 * heuristic(&next) + depth,
+                node: nodes.len() - 1,
+                board: next,
+            });
+        }
+    }
+    Outcome::Unsolvable
+}
+
+fn path_length(nodes: &[(Option<usize>, Vec<Move>)], mut node: usize) -> usize {
+    let mut length = 0;
+    while let (Some(parent), moves) = &nodes[node] {
+        length += moves.len();
+        node = *parent;
+    }
+    length
+}
+
+/// Walk back from the winning node to rebuild the moves from the start.
+fn solution(nodes: &[(Option<usize>, Vec<Move>)], mut node: usize) -> Solution {
+    let mut path = Vec::new();
+    loop {
+        let (parent, moves) = &nodes[node];
+        path.push(moves.clone());
+        match parent {
+            Some(parent) => node = *parent,
+            None => break,
+        }
+    }
+    Solution {
+        moves: path.into_iter().rev().flatten().collect(),
+        nodes: nodes.len(),
+    }
+}
+
+/// Whether `card` can go to the foundations without being missed on the
+/// table, because no card left there could be built on it.
+fn is_safe_to_send(board: &Board, card: &Card) -> bool {
+    let rules = &board.rules;
+    let rank = card.rank(rules.base_rank);
+    if rank <= 1 || rules.variant.building == Building::Suit {
+        return true;
+    }
+    // Every card of the opposite colour and rank just below must be home.
+    let needed = rules.variant.decks as usize;
+    (0..4)
+        .filter(|&suit| is_red_suit(suit) != card.is_red())
+        .all(|suit| {
+            board
+                .foundations
+                .iter()
+                .filter_map(|f| f.last())
+                .filter(|top| top.suit == suit && top.rank(rules.base_rank) + 1 >= rank)
+                .count()
+                >= needed
+        })
+}
+
+/// Send every safe card to the foundations, returning the moves made.
+pub fn auto_play(board: &mut Board) -> Vec<Move> {
+    let mut moves = Vec::new();
+    loop {
+        let mut next = None;
+        let sources = (0..board.cells.len())
+            .map(Pile::Cell)
+            .chain((0..board.cascades.len()).map(Pile::Cascade));
+        for from in sources {
+            let card = match from {
+                Pile::Cell(i) => board.cells[i].as_ref(),
+                Pile::Cascade(i) => board.cascades[i].last(),
+                Pile::Foundation(_) => None,
+            };
+            if let Some(card) = card {
+                if let Some(f) = board.foundation_for(card) {
+                    if is_safe_to_send(board, card) {
+                        next = Some(Move {
+                            from,
+                            to: Pile::Foundation(f),
+                            count: 1,
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+        match next {
+            Some(m) => {
+                board.apply(&m);
+                moves.push(m);
+            }
+            None => return moves,
+        }
+    }
+}
+
+/// Estimate how far `board` is from being won; lower is better.
+fn heuristic(board: &Board) -> i32 {
+    let base_rank = board.rules.base_rank;
+    let home: usize = board.foundations.iter().map(|f| f.len()).sum();
+    let total = 52 * board.rules.variant.decks as usize;
+    let mut score = 3 * (total - home) as i32;
+    score += (board.cells.len() - board.empty_cell_count()) as i32;
+
+    // Cards lying on a lower card keep it from its foundation.
+    for cascade in board.cascades.iter() {
+        let mut lowest = u8::MAX;
+        for card in cascade.iter() {
+            let rank = card.rank(base_rank);
+            if rank > lowest {
+                score += 2;
+            }
+            lowest = lowest.min(rank);
+        }
+    }
+    score
+}
+
+/// A hash of the position, the same for positions which only differ by the
+/// order of cascades or cells, or by which deck identical cards come from.
+fn fingerprint(board: &Board) -> u64 {
+    let code = |card: &Card| card.suit * 13 + card.value;
+    let mut cascades: Vec<Vec<u8>> = board
+        .cascades
+        .iter()
+        .map(|c| c.iter().map(code).collect())
+        .collect();
+    cascades.sort_unstable();
+    let mut cells: Vec<u8> = board.cells.iter().flatten().map(code).collect();
+    cells.sort_unstable();
+    let mut foundations: Vec<u8> = board
+        .foundations
+        .iter()
+        .map(|f| f.last().map_or(u8::MAX, code))
+        .collect();
+    foundations.sort_unstable();
+
+    let mut hasher = DefaultHasher::new();
+    cascades.hash(&mut hasher);
+    cells.hash(&mut hasher);
+    foundations.hash(&mut hasher);
+    hasher.finish()
+}
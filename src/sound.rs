@@ -0,0 +1,259 @@
+use crate::settings::Audio;
+use crate::storage;
+use ggez::audio::{SoundData, SoundSource, Source};
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The sounds played by the game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Effect {
+    Deal,
+    /// Cards sent to the foundations.
+    Send,
+    /// Cards picked up.
+    Pick,
+    /// Cards put down.
+    Drop,
+    /// Played over and over during the victory celebration.
+    Finale,
+    /// Cards put down where they cannot go.
+    Illegal,
+}
+
+impl Effect {
+    pub const ALL: [Effect; 6] = [
+        Effect::Deal,
+        Effect::Send,
+        Effect::Pick,
+        Effect::Drop,
+        Effect::Finale,
+        Effect::Illegal,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::Deal => "Deal",
+            Effect::Send => "Send",
+            Effect::Pick => "Pick up",
+            Effect::Drop => "Put down",
+            Effect::Finale => "Victory",
+            Effect::Illegal => "Illegal move",
+        }
+    }
+
+    /// The resource played by default, with its volume and pitch.
+    fn resource(&self) -> (&'static str, f32, f32) {
+        match self {
+            Effect::Deal => ("/deal.wav", 2., 2.),
+            Effect::Send => ("/deal.wav", 0.2, 1.),
+            Effect::Pick => ("/take.wav", 0.15, 1.7),
+            Effect::Drop => ("/drop.wav", 0.15, 1.7),
+            Effect::Finale => ("/take.wav", 0.6, 4.),
+            Effect::Illegal => ("/drop.wav", 0.4, 0.6),
+        }
+    }
+}
+
+/// Volume of each effect, from 0 to 1, on top of the master volume. The
+/// illegal move sound is off unless turned up.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectVolumes {
+    pub deal: f32,
+    pub send: f32,
+    pub pick: f32,
+    pub drop: f32,
+    pub finale: f32,
+    pub illegal: f32,
+}
+
+impl Default for EffectVolumes {
+    fn default() -> Self {
+        Self {
+            deal: 1.,
+            send: 1.,
+            pick: 1.,
+            drop: 1.,
+            finale: 1.,
+            illegal: 0.,
+        }
+    }
+}
+
+impl EffectVolumes {
+    pub fn get(&self, effect: Effect) -> f32 {
+        match effect {
+            Effect::Deal => self.deal,
+            Effect::Send => self.send,
+            Effect::Pick => self.pick,
+            Effect::Drop => self.drop,
+            Effect::Finale => self.finale,
+            Effect::Illegal => self.illegal,
+        }
+    }
+
+    pub fn get_mut(&mut self, effect: Effect) -> &mut f32 {
+        match effect {
+            Effect::Deal => &mut self.deal,
+            Effect::Send => &mut self.send,
+            Effect::Pick => &mut self.pick,
+            Effect::Drop => &mut self.drop,
+            Effect::Finale => &mut self.finale,
+            Effect::Illegal => &mut self.illegal,
+        }
+    }
+}
+
+/// Sound files replacing the sounds of the game, relative to the user config
+/// directory unless absolute.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundFiles {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deal: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pick: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finale: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illegal: Option<PathBuf>,
+}
+
+impl SoundFiles {
+    pub fn get(&self, effect: Effect) -> Option<&Path> {
+        match effect {
+            Effect::Deal => self.deal.as_deref(),
+            Effect::Send => self.send.as_deref(),
+            Effect::Pick => self.pick.as_deref(),
+            Effect::Drop => self.drop.as_deref(),
+            Effect::Finale => self.finale.as_deref(),
+            Effect::Illegal => self.illegal.as_deref(),
+        }
+    }
+}
+
+struct Sound {
+    data: SoundData,
+    volume: f32,
+    pitch: f32,
+}
+
+/// Owns the sounds of the game and plays them at the volumes set.
+pub struct Sounds {
+    sounds: Vec<Sound>,
+    /// Whether there is an audio device, sounds being silently dropped if
+    /// not.
+    enabled: bool,
+    /// Master volume, 0 when muted.
+    volume: f32,
+    effects: EffectVolumes,
+}
+
+impl Sounds {
+    /// Load the sounds, replaced by the files given in `audio` when they can
+    /// be read. Nothing is played unless `enabled`.
+    pub fn new(ctx: &mut Context, audio: &Audio, enabled: bool) -> Self {
+        let sounds = Effect::ALL
+            .iter()
+            .map(|effect| {
+                let user_file = audio
+                    .files
+                    .get(*effect)
+                    .and_then(storage::config_file)
+                    .and_then(|path| match fs::read(&path) {
+                        Ok(bytes) => {
+                            let data = SoundData::from_bytes(&bytes);
+                            if data.can_play() {
+                                Some(data)
+                            } else {
+                                eprintln!("Could not decode {}", path.display());
+                                None
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Could not read {}: {}", path.display(), e);
+                            None
+                        }
+                    });
+                match user_file {
+                    // Played as recorded, the defaults being tuned to the
+                    // bundled sounds.
+                    Some(data) => Sound {
+                        data,
+                        volume: 1.,
+                        pitch: 1.,
+                    },
+                    None => {
+                        let (resource, volume, pitch) = effect.resource();
+                        Sound {
+                            data: SoundData::new(ctx, resource).unwrap(),
+                            volume,
+                            pitch,
+                        }
+                    }
+                }
+            })
+            .collect();
+        Self {
+            sounds,
+            enabled,
+            volume: 1.,
+            effects: EffectVolumes::default(),
+        }
+    }
+
+    /// Use the volumes in `audio`, or none at all if `muted`.
+    pub fn configure(&mut self, audio: &Audio, muted: bool) {
+        self.volume = if muted || audio.muted {
+            0.
+        } else {
+            audio.volume
+        };
+        self.effects = audio.effects.clone();
+    }
+
+    fn sound(&self, effect: Effect) -> &Sound {
+        &self.sounds[effect as usize]
+    }
+
+    pub fn volume(&self, effect: Effect) -> f32 {
+        self.sound(effect).volume * self.effects.get(effect) * self.volume
+    }
+
+    /// A source for `effect`, set to its volume and pitch, if sounds can be
+    /// played at all.
+    pub fn source(&self, ctx: &mut Context, effect: Effect) -> Option<Source> {
+        if !self.enabled {
+            return None;
+        }
+        let sound = self.sound(effect);
+        match Source::from_data(ctx, sound.data.clone()) {
+            Ok(mut source) => {
+                source.set_volume(self.volume(effect));
+                source.set_pitch(sound.pitch);
+                Some(source)
+            }
+            Err(e) => {
+                eprintln!("Could not play a sound: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn play(&self, ctx: &mut Context, effect: Effect) {
+        if self.volume(effect) == 0. {
+            return;
+        }
+        if let Some(mut source) = self.source(ctx, effect) {
+            if let Err(e) = source.play_detached(ctx) {
+                eprintln!("Could not play a sound: {}", e);
+            }
+        }
+    }
+}
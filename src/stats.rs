@@ -0,0 +1,179 @@
+use crate::daily::Date;
+use crate::deal::DealId;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::Duration;
+
+const STATS_FILE: &str = "stats.json";
+
+/// How a daily challenge was completed.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DailyRecord {
+    pub date: Date,
+    pub deal: u32,
+    pub moves: u32,
+    pub seconds: u64,
+}
+
+impl DailyRecord {
+    pub fn time(&self) -> Duration {
+        Duration::from_secs(self.seconds)
+    }
+}
+
+/// The best results over the wins of a deal, each possibly from a different
+/// game.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DealBest {
+    /// The `Variant::id` of the deal.
+    pub variant: String,
+    pub deal: DealId,
+    pub moves: u32,
+    pub seconds: u64,
+    pub score: i32,
+}
+
+impl DealBest {
+    pub fn time(&self) -> Duration {
+        Duration::from_secs(self.seconds)
+    }
+}
+
+/// Statistics kept across games in the user data directory.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub played: u32,
+    pub won: u32,
+    /// Games left for another before being won, counted as lost.
+    #[serde(default)]
+    pub abandoned: u32,
+    /// Completed daily challenges, by date.
+    pub dailies: Vec<DailyRecord>,
+    /// Personal bests of the deals won.
+    #[serde(default)]
+    pub bests: Vec<DealBest>,
+}
+
+impl Stats {
+    pub fn load() -> Self {
+        storage::load(storage::data_file(STATS_FILE))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(storage::data_file(STATS_FILE), self)
+    }
+
+    pub fn daily(&self, date: Date) -> Option<&DailyRecord> {
+        self.dailies.iter().find(|d| d.date == date)
+    }
+
+    /// Record a completed daily challenge, keeping the best of several
+    /// completions of the same day.
+    pub fn record_daily(&mut self, record: DailyRecord) {
+        match self.dailies.iter_mut().find(|d| d.date == record.date) {
+            Some(best) => {
+                if (record.moves, record.seconds) < (best.moves, best.seconds) {
+                    *best = record;
+                }
+            }
+            None => {
+                self.dailies.push(record);
+                self.dailies.sort_by_key(|d| d.date);
+            }
+        }
+    }
+
+    pub fn best(&self, variant: &str, deal: DealId) -> Option<&DealBest> {
+        self.bests
+            .iter()
+            .find(|b| b.variant == variant && b.deal == deal)
+    }
+
+    /// Record a win of a deal, keeping the best moves, time and score of all
+    /// its wins.
+    pub fn record_best(&mut self, record: DealBest) {
+        match self
+            .bests
+            .iter_mut()
+            .find(|b| b.variant == record.variant && b.deal == record.deal)
+        {
+            Some(best) => {
+                best.moves = best.moves.min(record.moves);
+                best.seconds = best.seconds.min(record.seconds);
+                best.score = best.score.max(record.score);
+            }
+            None => self.bests.push(record),
+        }
+    }
+}
+
+/// `duration` as minutes and seconds.
+pub fn format_time(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(moves: u32, seconds: u64) -> DailyRecord {
+        DailyRecord {
+            date: Date {
+                year: 2021,
+                month: 8,
+                day: 14,
+            },
+            deal: 1,
+            moves,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn record_daily_keeps_the_better_completion() {
+        let mut stats = Stats::default();
+        stats.record_daily(daily(90, 300));
+        stats.record_daily(daily(100, 200));
+        stats.record_daily(daily(90, 250));
+        assert_eq!(stats.dailies.len(), 1);
+        let best = stats.daily(daily(0, 0).date).unwrap();
+        assert_eq!((best.moves, best.seconds), (90, 250));
+    }
+
+    fn best(variant: &str, moves: u32, seconds: u64, score: i32) -> DealBest {
+        DealBest {
+            variant: variant.to_string(),
+            deal: DealId::Number(617),
+            moves,
+            seconds,
+            score,
+        }
+    }
+
+    #[test]
+    fn record_best_only_improves() {
+        let mut stats = Stats::default();
+        stats.record_best(best("freecell", 90, 300, 500));
+        stats.record_best(best("freecell", 100, 200, 480));
+        stats.record_best(best("freecell", 85, 400, 520));
+        stats.record_best(best("bakers", 120, 600, 300));
+        assert_eq!(
+            stats.best("freecell", DealId::Number(617)),
+            Some(&best("freecell", 85, 200, 520))
+        );
+        assert_eq!(
+            stats.best("bakers", DealId::Number(617)),
+            Some(&best("bakers", 120, 600, 300))
+        );
+        assert_eq!(stats.best("freecell", DealId::Number(618)), None);
+    }
+
+    #[test]
+    fn times_are_minutes_and_seconds() {
+        assert_eq!(format_time(Duration::from_secs(0)), "0:00");
+        assert_eq!(format_time(Duration::from_secs(75)), "1:15");
+        assert_eq!(format_time(Duration::from_secs(3600)), "60:00");
+    }
+}
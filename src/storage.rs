@@ -0,0 +1,39 @@
+use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "freecell")
+}
+
+/// Path of a file kept in the user data directory.
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.data_dir().join(name))
+}
+
+/// Path of a file kept in the user config directory, or `name` itself if it
+/// is absolute.
+pub fn config_file(name: impl AsRef<Path>) -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().join(name))
+}
+
+/// Read a JSON file, falling back to the default value if it is missing or
+/// unreadable.
+pub fn load<T: DeserializeOwned + Default>(path: Option<PathBuf>) -> T {
+    path.and_then(|path| fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Write `value` to a JSON file, creating its directory if needed.
+pub fn save<T: Serialize>(path: Option<PathBuf>, value: &T) -> io::Result<()> {
+    let path = path.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec(value).map_err(io::Error::from)?;
+    fs::write(path, json)
+}
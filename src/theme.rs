@@ -0,0 +1,364 @@
+use crate::card::{Card, CARD_HEIGHT, CARD_WIDTH};
+use crate::components::Fill;
+use crate::notation;
+use crate::storage;
+use crate::tileset::{TileSet, TileSetError};
+use crate::variant::Variant;
+use ggez::conf::NumSamples;
+use ggez::graphics::{
+    self, BlendMode, Canvas, Color, DrawMode, DrawParam, Drawable, Image, Mesh, PxScale, Rect,
+    Text, TextFragment,
+};
+use ggez::{Context, GameResult};
+use nalgebra::{point, vector, Point2};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The theme drawn from the bundled card sheet.
+pub const DEFAULT_THEME: &str = "classic";
+/// Directory of the themes, in the user config directory.
+const THEMES_DIR: &str = "themes";
+const MANIFEST_FILE: &str = "theme.toml";
+const CLUBS: u8 = 2;
+const DIAMONDS: u8 = 3;
+/// Pixels along the edge of the card tiles left alone when recolouring.
+const TILE_EDGE: i32 = 3;
+const RANK_LABELS: [&str; 13] = [
+    "A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K",
+];
+const SUIT_SYMBOLS: [&str; 4] = ["\u{2660}", "\u{2665}", "\u{2663}", "\u{2666}"];
+const LABEL_SIZE: f32 = 20.;
+
+/// Ways of drawing the cards for players who tell colours apart poorly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CardStyle {
+    /// Clubs drawn green and diamonds blue.
+    pub four_colour: bool,
+    /// Large indices in the corner of the cards.
+    pub high_contrast: bool,
+}
+
+impl CardStyle {
+    /// The colour of the suit of `card`.
+    fn colour(&self, card: &Card) -> Color {
+        match (card.suit, self.four_colour) {
+            (CLUBS, true) => Color::from_rgb(0, 120, 40),
+            (DIAMONDS, true) => Color::from_rgb(0, 70, 200),
+            _ if card.is_red() => Color::from_rgb(200, 0, 0),
+            _ => Color::BLACK,
+        }
+    }
+}
+
+/// How the card sheet of a theme is laid out, read from the `theme.toml` of
+/// its directory. Tiles are given as `[column, row]` in the sheet.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Manifest {
+    /// The card sheet, relative to the theme directory.
+    sheet: PathBuf,
+    /// Size of a tile in the sheet, in pixels. Cards are drawn scaled to the
+    /// size of the table's slots.
+    tile_size: [i32; 2],
+    /// Row of the cards of each suit: spades, hearts, clubs and diamonds.
+    suit_rows: [i32; 4],
+    /// Column of each rank, from ace to king.
+    rank_columns: [i32; 13],
+    /// Tiles of single cards, by name such as `QH`, in place of the grid.
+    cards: HashMap<String, [i32; 2]>,
+    /// The tile drawn for empty piles.
+    empty: [i32; 2],
+    /// The back of the cards.
+    back: [i32; 2],
+    table: TableManifest,
+}
+
+/// The image drawn over the table, in the `[table]` section of a manifest.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TableManifest {
+    /// Relative to the theme directory.
+    image: Option<PathBuf>,
+    fill: Fill,
+}
+
+/// The tiles of the cards of a theme, and its table image.
+pub struct Theme {
+    pub tiles: TileSet<Option<Card>>,
+    pub table: Option<(Image, Fill)>,
+}
+
+impl Default for Manifest {
+    /// The layout of the bundled card sheet.
+    fn default() -> Self {
+        let mut rank_columns = [0; 13];
+        for (column, rank) in rank_columns.iter_mut().enumerate() {
+            *rank = column as i32;
+        }
+        Self {
+            sheet: PathBuf::from("cards.png"),
+            tile_size: [CARD_WIDTH, CARD_HEIGHT],
+            suit_rows: [0, 1, 2, 3],
+            rank_columns,
+            cards: HashMap::new(),
+            empty: [12, 4],
+            back: [0, 4],
+            table: TableManifest::default(),
+        }
+    }
+}
+
+fn themes_dir() -> Option<PathBuf> {
+    storage::config_file(THEMES_DIR)
+}
+
+/// The names of the themes to choose from: the default one, then those found
+/// in the themes directory.
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = themes_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(MANIFEST_FILE).is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != DEFAULT_THEME)
+        .collect();
+    names.sort();
+    names.insert(0, DEFAULT_THEME.to_string());
+    names
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, TileSetError> {
+    fs::read(path).map_err(|e| TileSetError::Io(format!("{}: {}", path.display(), e)))
+}
+
+fn read_image(ctx: &mut Context, path: &Path) -> Result<Image, TileSetError> {
+    Image::from_bytes(ctx, &read(path)?).map_err(|e| TileSetError::Image(e.to_string()))
+}
+
+/// Load the image at `path`, relative to the user config directory unless
+/// absolute, to cover the table with.
+pub fn table_image(ctx: &mut Context, path: &Path) -> Result<Image, TileSetError> {
+    let path = storage::config_file(path)
+        .ok_or_else(|| TileSetError::Io("No home directory".to_string()))?;
+    read_image(ctx, &path)
+}
+
+/// Load the theme called `name`, drawing the cards in `style`.
+pub fn load(ctx: &mut Context, name: &str, style: CardStyle) -> Result<Theme, TileSetError> {
+    if name == DEFAULT_THEME {
+        let image =
+            Image::new(ctx, "/cards.png").map_err(|e| TileSetError::Image(e.to_string()))?;
+        return Ok(Theme {
+            tiles: tileset(ctx, image, &Manifest::default(), style)?,
+            table: None,
+        });
+    }
+    let dir = themes_dir()
+        .map(|dir| dir.join(name))
+        .filter(|dir| dir.join(MANIFEST_FILE).is_file())
+        .ok_or_else(|| TileSetError::NoSuchTheme(name.to_string()))?;
+    let text = String::from_utf8_lossy(&read(&dir.join(MANIFEST_FILE))?).into_owned();
+    let manifest: Manifest =
+        toml::from_str(&text).map_err(|e| TileSetError::Manifest(e.to_string()))?;
+    let image = read_image(ctx, &dir.join(&manifest.sheet))?;
+    let table = match &manifest.table.image {
+        Some(path) => Some((read_image(ctx, &dir.join(path))?, manifest.table.fill)),
+        None => None,
+    };
+    Ok(Theme {
+        tiles: tileset(ctx, image, &manifest, style)?,
+        table,
+    })
+}
+
+/// The tiles of the cards as laid out in `manifest`, by suit and value.
+fn card_tiles(manifest: &Manifest) -> Result<HashMap<(u8, u8), Point2<i32>>, TileSetError> {
+    let mut tiles = HashMap::new();
+    for suit in 0..4 {
+        for value in 0..13 {
+            let index = point![
+                manifest.rank_columns[value as usize],
+                manifest.suit_rows[suit as usize]
+            ];
+            tiles.insert((suit, value), index);
+        }
+    }
+    for (name, [column, row]) in manifest.cards.iter() {
+        let card = notation::parse_card(name).map_err(|e| TileSetError::Manifest(e.to_string()))?;
+        tiles.insert((card.suit, card.value), point![*column, *row]);
+    }
+    Ok(tiles)
+}
+
+/// Register the tiles of every card of `image` as laid out in `manifest`,
+/// checking them all.
+fn tileset(
+    ctx: &mut Context,
+    image: Image,
+    manifest: &Manifest,
+    style: CardStyle,
+) -> Result<TileSet<Option<Card>>, TileSetError> {
+    let tiles = card_tiles(manifest)?;
+    let image = if style.four_colour {
+        four_colour(ctx, image, manifest, &tiles)?
+    } else {
+        image
+    };
+    let image = if style.high_contrast {
+        high_contrast(ctx, image, manifest, &tiles, style)?
+    } else {
+        image
+    };
+    let mut tileset = TileSet::new(image, manifest.tile_size)?;
+    tileset.scale_to(vector![CARD_WIDTH, CARD_HEIGHT]);
+    let decks = Variant::ALL.iter().map(|v| v.decks).max().unwrap_or(1);
+    for deck in 0..decks {
+        for suit in 0..4 {
+            for value in 0..13 {
+                let card = Card { suit, value, deck };
+                tileset.register_tile(Some(card), tiles[&(suit, value)])?;
+            }
+        }
+    }
+    tileset.register_tile(None, manifest.empty)?;
+    tileset.check_index(manifest.back)?;
+    Ok(tileset)
+}
+
+/// The large index drawn over the corner of `card` in high contrast, `size`
+/// pixels high.
+fn label(card: &Card, size: f32) -> Text {
+    let text = format!(
+        "{}{}",
+        RANK_LABELS[card.value as usize], SUIT_SYMBOLS[card.suit as usize]
+    );
+    Text::new(TextFragment::new(text).scale(PxScale::from(size)))
+}
+
+/// Draw a large index over the top left corner of every card of the sheet,
+/// in the colour of its suit on a white background.
+fn high_contrast(
+    ctx: &mut Context,
+    image: Image,
+    manifest: &Manifest,
+    tiles: &HashMap<(u8, u8), Point2<i32>>,
+    style: CardStyle,
+) -> Result<Image, TileSetError> {
+    let image_error = |e: ggez::GameError| TileSetError::Image(e.to_string());
+    let format = graphics::get_window_color_format(ctx);
+    let canvas = Canvas::new(ctx, image.width(), image.height(), NumSamples::One, format)
+        .map_err(image_error)?;
+    let screen = graphics::screen_coordinates(ctx);
+    graphics::set_canvas(ctx, Some(&canvas));
+    let drawn = draw_indices(ctx, image, manifest, tiles, style);
+    graphics::set_canvas(ctx, None);
+    graphics::set_screen_coordinates(ctx, screen).map_err(image_error)?;
+    drawn.map_err(image_error)?;
+    canvas.to_image(ctx).map_err(image_error)
+}
+
+/// Draw `image` with the indices of `high_contrast` over it, on the canvas
+/// in use.
+fn draw_indices(
+    ctx: &mut Context,
+    mut image: Image,
+    manifest: &Manifest,
+    tiles: &HashMap<(u8, u8), Point2<i32>>,
+    style: CardStyle,
+) -> GameResult<()> {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    graphics::set_screen_coordinates(ctx, Rect::new(0., 0., width, height))?;
+    graphics::clear(ctx, Color::new(0., 0., 0., 0.));
+    // Copied as is, transparent corners included.
+    image.set_blend_mode(Some(BlendMode::Replace));
+    graphics::draw(ctx, &image, DrawParam::default())?;
+
+    let [tile_width, tile_height] = manifest.tile_size;
+    // As large on the table whatever the size of the tiles in the sheet.
+    let scale = tile_height as f32 / CARD_HEIGHT as f32;
+    for ((suit, value), index) in tiles.iter() {
+        let card = Card {
+            suit: *suit,
+            value: *value,
+            deck: 0,
+        };
+        let text = label(&card, LABEL_SIZE * scale);
+        let size = text.dimensions(ctx);
+        let x = (index.x * tile_width) as f32;
+        let y = (index.y * tile_height) as f32;
+        let background = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                x + 2. * scale,
+                y + 2. * scale,
+                size.w + 6. * scale,
+                size.h + 2. * scale,
+            ),
+            Color::WHITE,
+        )?;
+        graphics::draw(ctx, &background, DrawParam::default())?;
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParam::default()
+                .dest(point![x + 5. * scale, y + 3. * scale])
+                .color(style.colour(&card)),
+        )?;
+    }
+    Ok(())
+}
+
+/// Recolour the clubs of the sheet green and its diamonds blue, in place of
+/// black and red.
+fn four_colour(
+    ctx: &mut Context,
+    image: Image,
+    manifest: &Manifest,
+    tiles: &HashMap<(u8, u8), Point2<i32>>,
+) -> Result<Image, TileSetError> {
+    let image_error = |e: ggez::GameError| TileSetError::Image(e.to_string());
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let mut pixels = image.to_rgba8(ctx).map_err(image_error)?;
+    let [tile_width, tile_height] = manifest.tile_size;
+    for ((suit, _), index) in tiles.iter() {
+        let recolour: fn([u8; 3]) -> Option<[u8; 3]> = match *suit {
+            // Black ink, dark and grey.
+            CLUBS => |[r, g, b]| {
+                let (low, high) = (r.min(g).min(b), r.max(g).max(b));
+                (high < 100 && high - low < 30).then(|| [0, 90 + high / 2, 30 + high / 4])
+            },
+            // Red ink.
+            DIAMONDS => |[r, g, b]| (r > 120 && g < 100 && b < 100).then_some([g / 2, r / 3, r]),
+            _ => continue,
+        };
+        let left = index.x * tile_width;
+        let top = index.y * tile_height;
+        // Tiles out of the sheet are reported when registered.
+        for y in (top + TILE_EDGE).max(0)..(top + tile_height - TILE_EDGE).min(height) {
+            for x in (left + TILE_EDGE).max(0)..(left + tile_width - TILE_EDGE).min(width) {
+                let i = 4 * (y * width + x) as usize;
+                if let Some(colour) = recolour([pixels[i], pixels[i + 1], pixels[i + 2]]) {
+                    pixels[i..i + 3].copy_from_slice(&colour);
+                }
+            }
+        }
+    }
+    Image::from_rgba8(ctx, width as u16, height as u16, &pixels).map_err(image_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_name_the_back_of_the_cards() {
+        let manifest: Manifest = toml::from_str("empty = [12, 4]\nback = [1, 4]\n").unwrap();
+        assert_eq!(manifest.back, [1, 4]);
+        assert!(toml::from_str::<Manifest>("front = [1, 4]\n").is_err());
+    }
+}
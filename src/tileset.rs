@@ -3,29 +3,64 @@ use nalgebra::{point, vector, Point2, Vector2};
 use std::collections::HashMap;
 use std::hash::Hash;
 
+/// Colour of the shadows cast by tiles.
+const SHADOW_COLOR: Color = Color::new(0., 0., 0., 0.35);
+
 /// A set of tiles made from a tilesheet image.
 pub struct TileSet<Key: Hash + Eq> {
-    // tile_size: Vector2<i32>,
+    tile_size: Vector2<i32>,
     tile_cache: HashMap<Key, Point2<i32>>,
     sheet_dimensions: Vector2<i32>,
+    /// Scale applied to every tile, on top of the one in `TileParams`.
+    scale: Vector2<f32>,
     spritebatch: SpriteBatch,
 }
 
 impl<Key: Hash + Eq> TileSet<Key> {
     /// Create a new `TileSet` from an image and tile size.
-    pub fn new(sheet: Image, tile_size: impl Into<Vector2<i32>>) -> Self {
+    pub fn new(sheet: Image, tile_size: impl Into<Vector2<i32>>) -> Result<Self, TileSetError> {
         let tile_size = tile_size.into();
+        if tile_size.x <= 0
+            || tile_size.y <= 0
+            || tile_size.x > sheet.width() as i32
+            || tile_size.y > sheet.height() as i32
+        {
+            return Err(TileSetError::BadTileSize);
+        }
         let sheet_dimensions = vector![
             sheet.width() as i32 / tile_size.x,
             sheet.height() as i32 / tile_size.y
         ];
 
-        Self {
-            // tile_size,
+        Ok(Self {
+            tile_size,
             tile_cache: HashMap::new(),
             sheet_dimensions,
+            scale: vector![1.0, 1.0],
             spritebatch: SpriteBatch::new(sheet),
+        })
+    }
+
+    /// Draw every tile at `size` rather than at its size in the tilesheet.
+    pub fn scale_to(&mut self, size: impl Into<Vector2<i32>>) {
+        let size = size.into();
+        self.scale = vector![
+            size.x as f32 / self.tile_size.x as f32,
+            size.y as f32 / self.tile_size.y as f32
+        ];
+    }
+
+    /// Check that `index` is a tile of the tilesheet.
+    pub fn check_index(&self, index: impl Into<Point2<i32>>) -> Result<(), TileSetError> {
+        let index = index.into();
+        if index.x < 0
+            || index.y < 0
+            || index.x >= self.sheet_dimensions.x
+            || index.y >= self.sheet_dimensions.y
+        {
+            return Err(TileSetError::OutOfRange);
         }
+        Ok(())
     }
 
     /// Register a tile from the tilesheet to the `TileSet` with the lookup
@@ -36,10 +71,7 @@ impl<Key: Hash + Eq> TileSet<Key> {
         index: impl Into<Point2<i32>>,
     ) -> Result<(), TileSetError> {
         let index = index.into();
-
-        if index.x > self.sheet_dimensions.x || index.y > self.sheet_dimensions.y {
-            return Err(TileSetError::OutOfRange);
-        }
+        self.check_index(index)?;
 
         self.tile_cache.insert(key, index);
 
@@ -62,6 +94,7 @@ impl<Key: Hash + Eq> TileSet<Key> {
         let options = options.map(|tp| tp.into()).unwrap_or(TileParams {
             color: None,
             scale: None,
+            shadow: None,
         });
 
         let coords = draw_location.into();
@@ -77,8 +110,17 @@ impl<Key: Hash + Eq> TileSet<Key> {
                 normal_y,
             ))
             .color(options.color.unwrap_or(Color::WHITE))
-            .scale(options.scale.unwrap_or_else(|| vector![1.0, 1.0]));
-
+            .scale(
+                options
+                    .scale
+                    .unwrap_or_else(|| vector![1.0, 1.0])
+                    .component_mul(&self.scale),
+            );
+
+        if let Some(offset) = options.shadow {
+            let dest = point![coords.x as f32 + offset.x, coords.y as f32 + offset.y];
+            self.spritebatch.add(d.dest(dest).color(SHADOW_COLOR));
+        }
         self.spritebatch.add(d);
 
         Ok(())
@@ -103,11 +145,17 @@ pub struct TileParams {
     pub color: Option<Color>,
     /// Scale factor for drawing. Default is `1.0` (no scaling).
     pub scale: Option<Vector2<f32>>,
+    /// Offset of a shadow drawn under the tile, if any.
+    pub shadow: Option<Vector2<f32>>,
 }
 
 impl From<(Option<Color>, Option<Vector2<f32>>)> for TileParams {
     fn from((color, scale): (Option<Color>, Option<Vector2<f32>>)) -> TileParams {
-        TileParams { color, scale }
+        TileParams {
+            color,
+            scale,
+            shadow: None,
+        }
     }
 }
 
@@ -116,6 +164,7 @@ impl From<(Option<Color>, Vector2<f32>)> for TileParams {
         TileParams {
             color,
             scale: Some(scale),
+            shadow: None,
         }
     }
 }
@@ -125,6 +174,7 @@ impl From<(Color, Option<Vector2<f32>>)> for TileParams {
         TileParams {
             color: Some(color),
             scale,
+            shadow: None,
         }
     }
 }
@@ -134,29 +184,42 @@ impl From<(Color, Vector2<f32>)> for TileParams {
         TileParams {
             color: Some(color),
             scale: Some(scale),
+            shadow: None,
         }
     }
 }
 
-/// Possible errors from `TileSet` operations.
-#[derive(Debug, Clone, Copy)]
+/// Possible errors from `TileSet` operations, and from loading the tilesheets
+/// of card themes.
+#[derive(Debug, Clone)]
 pub enum TileSetError {
     /// The tile position to register was outside the tilesheet bounds.
     OutOfRange,
     /// Tile not found.
     TileNotFound,
+    /// The tile size was empty or larger than the tilesheet.
+    BadTileSize,
+    /// A theme was not found, by name.
+    NoSuchTheme(String),
+    /// A file of a theme could not be read.
+    Io(String),
+    /// The tilesheet image could not be decoded.
+    Image(String),
+    /// The manifest describing a tilesheet was invalid.
+    Manifest(String),
 }
 
 impl std::fmt::Display for TileSetError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                TileSetError::OutOfRange => "Position out of range of tilesheet dimensions",
-                TileSetError::TileNotFound => "Tile not found during lookup",
-            }
-        )
+        match self {
+            TileSetError::OutOfRange => write!(f, "Position out of range of tilesheet dimensions"),
+            TileSetError::TileNotFound => write!(f, "Tile not found during lookup"),
+            TileSetError::BadTileSize => write!(f, "Tile size does not fit the tilesheet"),
+            TileSetError::NoSuchTheme(name) => write!(f, "No theme named {}", name),
+            TileSetError::Io(e) => write!(f, "{}", e),
+            TileSetError::Image(e) => write!(f, "Invalid tilesheet: {}", e),
+            TileSetError::Manifest(e) => write!(f, "Invalid theme manifest: {}", e),
+        }
     }
 }
 
@@ -0,0 +1,314 @@
+use crate::board::Board;
+use crate::card::Card;
+use crate::deal::{Deal, DealId};
+use crate::notation;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::Stylize;
+use crossterm::{cursor, execute, terminal};
+use rand::prelude::*;
+use std::io::{self, BufRead, Write};
+
+const SUITS: [&str; 4] = ["♠", "♥", "♣", "♦"];
+const RANKS: [&str; 13] = [
+    "A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K",
+];
+const HELP: &str = "Keys: source then destination pile, Esc cancel, n new deal, q quit";
+
+/// A game played in the terminal, on the same `Board` as the solver.
+///
+/// Moves are made by pressing the key of the pile to take cards from, then
+/// that of the pile to put them on, as in standard move notation.
+pub struct Tui {
+    board: Board,
+    deal_id: Option<DealId>,
+    selected: Option<char>,
+    moves: u32,
+    message: String,
+}
+
+impl Tui {
+    pub fn new(deal: &Deal, deal_id: Option<DealId>) -> Self {
+        Self {
+            board: Board::from(deal),
+            deal_id,
+            selected: None,
+            moves: 0,
+            message: HELP.to_string(),
+        }
+    }
+
+    fn new_deal(&mut self) {
+        let id = DealId::Seed(rand::thread_rng().gen());
+        *self = Tui::new(&Deal::from_id(self.board.rules.variant, id), Some(id));
+    }
+
+    /// Handle a key press, returning false to quit.
+    pub fn key(&mut self, key: char) -> bool {
+        match key {
+            'q' => return false,
+            'n' => self.new_deal(),
+            '\x1b' => {
+                self.selected = None;
+                self.message = HELP.to_string();
+            }
+            _ if self.board.is_won() => {}
+            _ => match self.selected.take() {
+                None if self.is_pile_key(key) && key != notation::FOUNDATIONS => {
+                    self.selected = Some(key);
+                    self.message = format!("Move from {} to...", key);
+                }
+                None => self.message = format!("No pile {}", key),
+                Some(from) => self.play(from, key),
+            },
+        }
+        true
+    }
+
+    fn is_pile_key(&self, key: char) -> bool {
+        key == notation::FOUNDATIONS
+            || notation::CASCADES
+                .find(key)
+                .is_some_and(|i| i < self.board.cascades.len())
+            || notation::CELLS
+                .find(key)
+                .is_some_and(|i| i < self.board.cells.len())
+    }
+
+    fn play(&mut self, from: char, to: char) {
+        let text: String = [from, to].iter().collect();
+        match notation::parse_move(&self.board, &text) {
+            Ok(m) if self.board.is_legal(&m) => {
+                self.board.apply(&m);
+                self.moves += 1;
+                self.message = if self.board.is_won() {
+                    format!("You won in {} moves! Press n for a new deal", self.moves)
+                } else {
+                    format!("Moved {}", text)
+                };
+            }
+            Ok(_) => self.message = format!("Cannot move {}", text),
+            Err(e) => self.message = e.to_string(),
+        }
+    }
+
+    fn card(card: Option<&Card>, colour: bool) -> String {
+        let card = match card {
+            Some(card) => card,
+            None => return "  ·".to_string(),
+        };
+        let text = format!(
+            "{:>2}{}",
+            RANKS[card.value as usize], SUITS[card.suit as usize]
+        );
+        match (colour, card.is_red()) {
+            (true, true) => text.red().to_string(),
+            _ => text,
+        }
+    }
+
+    /// The label of a pile, marked when it is the selected source.
+    fn label(&self, key: char, colour: bool) -> String {
+        let text = format!("  {}", key);
+        match (self.selected == Some(key), colour) {
+            (true, true) => text.reverse().to_string(),
+            (true, false) => format!(" *{}", key),
+            _ => text,
+        }
+    }
+
+    /// Draw the table as lines of text, with ANSI colours if `colour`.
+    pub fn render(&self, colour: bool) -> String {
+        let board = &self.board;
+        let mut lines = Vec::new();
+        let deal = match self.deal_id {
+            Some(DealId::Number(n)) => format!("    Deal #{}", n),
+            _ => String::new(),
+        };
+        lines.push(format!(
+            "{}{}    Moves: {}",
+            board.rules.variant.name, deal, self.moves
+        ));
+        lines.push(String::new());
+
+        let cell_keys = notation::CELLS.chars().take(board.cells.len());
+        let mut labels: Vec<String> = cell_keys.map(|k| self.label(k, colour)).collect();
+        labels.push("   ".to_string());
+        labels.extend(
+            (0..board.foundations.len()).map(|_| self.label(notation::FOUNDATIONS, colour)),
+        );
+        lines.push(labels.join(" "));
+        let mut top: Vec<String> = board
+            .cells
+            .iter()
+            .map(|c| Tui::card(c.as_ref(), colour))
+            .collect();
+        top.push("   ".to_string());
+        top.extend(
+            board
+                .foundations
+                .iter()
+                .map(|f| Tui::card(f.last(), colour)),
+        );
+        lines.push(top.join(" "));
+        lines.push(String::new());
+
+        let cascade_keys = notation::CASCADES.chars().take(board.cascades.len());
+        let labels: Vec<String> = cascade_keys.map(|k| self.label(k, colour)).collect();
+        lines.push(labels.join(" "));
+        let depth = board.cascades.iter().map(Vec::len).max().unwrap_or(0);
+        for row in 0..depth.max(1) {
+            let cards: Vec<String> = board
+                .cascades
+                .iter()
+                .map(|c| match c.get(row) {
+                    Some(card) => Tui::card(Some(card), colour),
+                    None if row == 0 => Tui::card(None, colour),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            lines.push(cards.join(" ").trim_end().to_string());
+        }
+        lines.push(String::new());
+        lines.push(self.message.clone());
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Restores the terminal when the interactive game ends, even on a panic.
+struct RawMode;
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Play in the terminal, one key press at a time.
+pub fn run(mut tui: Tui) -> io::Result<()> {
+    let map_err = |e: crossterm::ErrorKind| io::Error::other(e.to_string());
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode().map_err(map_err)?;
+    let _raw_mode = RawMode;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide).map_err(map_err)?;
+
+    loop {
+        execute!(
+            stdout,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )
+        .map_err(map_err)?;
+        // Raw mode does not return the cursor to the start of a new line.
+        write!(stdout, "{}", tui.render(true).replace('\n', "\r\n"))?;
+        stdout.flush()?;
+
+        let key = match event::read().map_err(map_err)? {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            }) => 'q',
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) => c.to_ascii_lowercase(),
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc, ..
+            }) => '\x1b',
+            _ => continue,
+        };
+        if !tui.key(key) {
+            return Ok(());
+        }
+    }
+}
+
+/// Play from lines of keys read on the standard input, printing the table
+/// as plain text after each line, for scripts and tests.
+pub fn run_plain(mut tui: Tui) -> io::Result<()> {
+    print!("{}", tui.render(false));
+    for line in io::stdin().lock().lines() {
+        for key in line?.chars().filter(|c| !c.is_whitespace()) {
+            if !tui.key(key.to_ascii_lowercase()) {
+                return Ok(());
+            }
+        }
+        print!("{}", tui.render(false));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::Variant;
+
+    fn deal_one() -> Tui {
+        Tui::new(
+            &Deal::numbered(Variant::FREECELL, 1),
+            Some(DealId::Number(1)),
+        )
+    }
+
+    #[test]
+    fn renders_the_opening_layout() {
+        let text = deal_one().render(false);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Freecell    Deal #1    Moves: 0");
+        assert_eq!(lines[2], "  a   b   c   d       h   h   h   h");
+        assert_eq!(lines[3], "  ·   ·   ·   ·       ·   ·   ·   ·");
+        assert_eq!(lines[5], "  1   2   3   4   5   6   7   8");
+        assert_eq!(lines[6], " J♦  2♦  9♥  J♣  5♦  7♥  7♣  5♥");
+        assert_eq!(lines[12], " 6♠  9♣  2♥  6♥");
+        assert_eq!(lines[14], HELP);
+    }
+
+    #[test]
+    fn colours_hearts_and_diamonds() {
+        let card = |suit| Card {
+            suit,
+            value: 0,
+            deck: 0,
+        };
+        assert_eq!(Tui::card(Some(&card(0)), true), " A♠");
+        assert_ne!(Tui::card(Some(&card(1)), true), " A♥");
+        assert_eq!(Tui::card(Some(&card(1)), false), " A♥");
+        assert_eq!(Tui::card(Some(&card(2)), true), " A♣");
+        assert_ne!(Tui::card(Some(&card(3)), true), " A♦");
+    }
+
+    #[test]
+    fn moves_cards_from_pile_keys() {
+        let mut tui = deal_one();
+        assert!(tui.key('1'));
+        assert_eq!(tui.message, "Move from 1 to...");
+        assert!(tui.render(true).contains(&"  1".reverse().to_string()));
+        assert!(tui.render(false).contains(" *1"));
+        tui.key('a');
+        assert_eq!(tui.message, "Moved 1a");
+        let text = tui.render(false);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Freecell    Deal #1    Moves: 1");
+        assert_eq!(lines[3], " 6♠   ·   ·   ·       ·   ·   ·   ·");
+        assert_eq!(lines[12], "     9♣  2♥  6♥");
+    }
+
+    #[test]
+    fn refuses_bad_keys_and_moves() {
+        let mut tui = deal_one();
+        tui.key('x');
+        assert_eq!(tui.message, "No pile x");
+        tui.key('h');
+        assert_eq!(tui.message, "No pile h");
+        // The 6♠ does not go on the 9♣.
+        tui.key('1');
+        tui.key('2');
+        assert_eq!(tui.message, "Cannot move 12");
+        assert_eq!(tui.moves, 0);
+        tui.key('1');
+        tui.key('\x1b');
+        assert_eq!(tui.selected, None);
+        assert_eq!(tui.message, HELP);
+        assert!(!tui.key('q'));
+    }
+}
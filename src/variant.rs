@@ -0,0 +1,289 @@
+use crate::card::Card;
+use serde::{Deserialize, Serialize};
+
+/// How cards are built down on cascades.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Building {
+    /// Alternating red and black, as in the classic game.
+    Alternating,
+    /// Within the same suit.
+    Suit,
+}
+
+/// Which cards may be put on an empty cascade.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EmptyCascade {
+    AnyCard,
+    /// Only the highest rank (a king, unless the base rank differs).
+    KingsOnly,
+}
+
+/// The rank foundations are started from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BaseRank {
+    Ace,
+    /// The rank of the first card dealt; the other cards of that rank start
+    /// on the foundations and ranks wrap around from king to ace.
+    FirstDealt,
+}
+
+/// The shape of a game: how many decks are dealt and how many piles of
+/// each kind sit on the table.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Variant {
+    /// Short lowercase name, used on the command line and in saved games.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub decks: u8,
+    pub cascades: usize,
+    pub cells: usize,
+    /// Number of free cells dealt a card at the start of the game.
+    pub prefilled_cells: usize,
+    pub building: Building,
+    pub empty_cascade: EmptyCascade,
+    pub base_rank: BaseRank,
+    /// A seed whose shuffle is known to be winnable, dealt when no other
+    /// winnable deal is found in time.
+    pub solvable_seed: u64,
+}
+
+impl Variant {
+    pub const FREECELL: Variant = Variant {
+        id: "freecell",
+        name: "Freecell",
+        decks: 1,
+        cascades: 8,
+        cells: 4,
+        prefilled_cells: 0,
+        building: Building::Alternating,
+        empty_cascade: EmptyCascade::AnyCard,
+        base_rank: BaseRank::Ace,
+        solvable_seed: 0,
+    };
+    pub const DOUBLE_FREECELL: Variant = Variant {
+        id: "double",
+        name: "Double Freecell",
+        decks: 2,
+        cascades: 10,
+        cells: 8,
+        ..Variant::FREECELL
+    };
+    pub const FORECELL: Variant = Variant {
+        id: "forecell",
+        name: "Forecell",
+        prefilled_cells: 4,
+        empty_cascade: EmptyCascade::KingsOnly,
+        ..Variant::FREECELL
+    };
+    pub const PENGUIN: Variant = Variant {
+        id: "penguin",
+        name: "Penguin",
+        cascades: 7,
+        cells: 7,
+        building: Building::Suit,
+        empty_cascade: EmptyCascade::KingsOnly,
+        base_rank: BaseRank::FirstDealt,
+        ..Variant::FREECELL
+    };
+    pub const BAKERS_GAME: Variant = Variant {
+        id: "bakers",
+        name: "Baker's Game",
+        building: Building::Suit,
+        solvable_seed: 1,
+        ..Variant::FREECELL
+    };
+
+    /// Every variant that can be selected, in cycling order.
+    pub const ALL: &'static [Variant] = &[
+        Variant::FREECELL,
+        Variant::DOUBLE_FREECELL,
+        Variant::FORECELL,
+        Variant::PENGUIN,
+        Variant::BAKERS_GAME,
+    ];
+
+    /// One foundation per suit per deck.
+    pub fn foundations(&self) -> usize {
+        4 * self.decks as usize
+    }
+
+    pub fn from_id(id: &str) -> Option<Variant> {
+        Variant::ALL.iter().find(|v| v.id == id).copied()
+    }
+
+    /// The variant following this one in `Variant::ALL`.
+    pub fn next(&self) -> Variant {
+        let i = Variant::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Variant::ALL[(i + 1) % Variant::ALL.len()]
+    }
+}
+
+/// The rules of a game in progress: its variant along with the foundation
+/// base rank that was settled when dealing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rules {
+    pub variant: Variant,
+    pub base_rank: u8,
+}
+
+impl Rules {
+    pub fn new(variant: Variant, base_rank: u8) -> Self {
+        Self { variant, base_rank }
+    }
+
+    /// Whether `card` can be put on `previous` at the bottom of a cascade.
+    pub fn builds_down(&self, card: &Card, previous: &Card) -> bool {
+        match self.variant.building {
+            Building::Alternating => previous.follows_alternating(card, self.base_rank),
+            Building::Suit => previous.follows(card, self.base_rank),
+        }
+    }
+
+    /// Whether `card` can be put on an empty cascade.
+    pub fn starts_cascade(&self, card: &Card) -> bool {
+        match self.variant.empty_cascade {
+            EmptyCascade::AnyCard => true,
+            EmptyCascade::KingsOnly => card.rank(self.base_rank) == 12,
+        }
+    }
+
+    /// Whether `card` can be put on a foundation topped by `top`.
+    pub fn builds_up(&self, card: &Card, top: Option<&Card>) -> bool {
+        match top {
+            Some(top) => card.follows(top, self.base_rank),
+            None => card.value == self.base_rank,
+        }
+    }
+
+    /// How many cards can be moved at once between cascades, as if moving
+    /// them one by one through the empty cells and cascades.
+    pub fn movable_cards(&self, empty_cells: usize, empty_cascades: usize) -> usize {
+        match self.variant.empty_cascade {
+            EmptyCascade::AnyCard => 2usize.pow(empty_cascades as u32) * (empty_cells + 1),
+            EmptyCascade::KingsOnly => empty_cells + 1,
+        }
+    }
+
+    /// Which cards can fill an empty cascade, to explain a refused move.
+    pub fn empty_cascade_rule(&self) -> String {
+        const RANKS: [&str; 13] = [
+            "aces", "2s", "3s", "4s", "5s", "6s", "7s", "8s", "9s", "10s", "jacks", "queens",
+            "kings",
+        ];
+        match self.variant.empty_cascade {
+            EmptyCascade::AnyCard => "Any card can fill an empty cascade".to_string(),
+            EmptyCascade::KingsOnly => format!(
+                "Only {} can fill an empty cascade",
+                RANKS[(self.base_rank as usize + 12) % 13]
+            ),
+        }
+    }
+}
+
+/// Whether cards may be taken back from the foundations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Takeback {
+    Allowed,
+    Forbidden,
+    /// Allowed, but each card taken back costs `TAKEBACK_PENALTY` points.
+    Penalized,
+}
+
+/// Points lost for each card taken back from a foundation under
+/// `Takeback::Penalized`.
+pub const TAKEBACK_PENALTY: i32 = 15;
+
+impl Takeback {
+    pub fn next(&self) -> Takeback {
+        match self {
+            Takeback::Allowed => Takeback::Forbidden,
+            Takeback::Forbidden => Takeback::Penalized,
+            Takeback::Penalized => Takeback::Allowed,
+        }
+    }
+}
+
+impl std::fmt::Display for Takeback {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Takeback::Allowed => "allowed",
+                Takeback::Forbidden => "forbidden",
+                Takeback::Penalized => "penalized",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::deal::Deal;
+    use crate::solver::{self, Limits, Outcome};
+
+    #[test]
+    fn solvable_seeds_are_winnable() {
+        let limits = Limits {
+            nodes: 1_000_000,
+            deadline: None,
+        };
+        for variant in Variant::ALL {
+            let board = Board::from(&Deal::seeded(*variant, variant.solvable_seed));
+            let outcome = solver::solve(&board, &limits);
+            assert!(
+                matches!(outcome, Outcome::Solved(_)),
+                "{} seed {}: {:?}",
+                variant.name,
+                variant.solvable_seed,
+                outcome
+            );
+        }
+    }
+
+    fn card(value: u8) -> Card {
+        Card {
+            suit: 0,
+            value,
+            deck: 0,
+        }
+    }
+
+    #[test]
+    fn empty_cascades_follow_the_base_rank() {
+        let freecell = Rules::new(Variant::FREECELL, 0);
+        assert!(freecell.starts_cascade(&card(4)));
+        let forecell = Rules::new(Variant::FORECELL, 0);
+        assert!(forecell.starts_cascade(&card(12)));
+        assert!(!forecell.starts_cascade(&card(11)));
+        // Built down from the 8, so the 7 comes last.
+        let penguin = Rules::new(Variant::PENGUIN, 7);
+        assert!(penguin.starts_cascade(&card(6)));
+        assert!(!penguin.starts_cascade(&card(12)));
+    }
+
+    #[test]
+    fn empty_cascades_only_double_moves_when_any_card_fills_them() {
+        assert_eq!(Rules::new(Variant::FREECELL, 0).movable_cards(2, 1), 6);
+        assert_eq!(Rules::new(Variant::FORECELL, 0).movable_cards(2, 1), 3);
+    }
+
+    #[test]
+    fn empty_cascade_rule_names_the_last_rank() {
+        assert_eq!(
+            Rules::new(Variant::FORECELL, 0).empty_cascade_rule(),
+            "Only kings can fill an empty cascade"
+        );
+        assert_eq!(
+            Rules::new(Variant::PENGUIN, 7).empty_cascade_rule(),
+            "Only 7s can fill an empty cascade"
+        );
+        assert_eq!(
+            Rules::new(Variant::PENGUIN, 1).empty_cascade_rule(),
+            "Only aces can fill an empty cascade"
+        );
+    }
+}